@@ -0,0 +1,33 @@
+//! Benchmarks for filling rows of pixels with a color, eg the background
+//! of a bar.
+// Cargo builds benchmarks with `cfg(test)` but without the `#[test]`
+// functions, so the imports of the unit tests are unused.
+#![allow(unused_imports)]
+
+include!("common/preamble.rs");
+
+use criterion::{black_box, Criterion};
+use color::Color;
+
+/// About as wide as a bar on a big screen.
+const ROW_WIDTH: usize = 3840;
+
+fn fill_row(c: &mut Criterion) {
+    let color = Color::rgba(40, 44, 52, 200);
+    let mut row = vec![0; ROW_WIDTH];
+    c.bench_function("fill row with as_cairo_argb32", |b| {
+        // like in a drawing loop that is too big for the compiler to see
+        // that the color is the same for every pixel
+        b.iter(|| {
+            for pixel in row.iter_mut() {
+                *pixel = black_box(color).as_cairo_argb32();
+            }
+        })
+    });
+    c.bench_function("fill row with PackedColor", |b| {
+        b.iter(|| black_box(color).mul_alpha_into_u32().fill_row(&mut row))
+    });
+}
+
+criterion_group!(benches, fill_row);
+criterion_main!(benches);
@@ -0,0 +1,32 @@
+//! Benchmarks for parsing colors, eg when loading a big theme.
+// Cargo builds benchmarks with `cfg(test)` but without the `#[test]`
+// functions, so the imports of the unit tests are unused.
+#![allow(unused_imports)]
+
+include!("common/preamble.rs");
+
+use criterion::{black_box, Criterion};
+use color::Color;
+
+fn parse_hex(c: &mut Criterion) {
+    c.bench_function("parse 6 digit hex", |b| {
+        b.iter(|| Color::parse(black_box("#1a2b3c")))
+    });
+    c.bench_function("parse 8 digit hex", |b| {
+        b.iter(|| Color::parse(black_box("0x801a2b3c")))
+    });
+}
+
+fn parse_cached(c: &mut Criterion) {
+    for &s in &["#1a2b3c", "cornflowerblue", "hsla(210, 50%, 40%, 0.8)"] {
+        c.bench_function(&format!("parse {}", s), |b| {
+            b.iter(|| Color::parse(black_box(s)))
+        });
+        c.bench_function(&format!("parse_cached {}", s), |b| {
+            b.iter(|| Color::parse_cached(black_box(s)))
+        });
+    }
+}
+
+criterion_group!(benches, parse_hex, parse_cached);
+criterion_main!(benches);
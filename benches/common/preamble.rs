@@ -0,0 +1,31 @@
+// The parts the benchmarks share, included with `include!` because the
+// crates have to be declared at the root of each benchmark.
+//
+// way-cooler is only a binary, so the color modules are included directly.
+
+#[macro_use]
+extern crate criterion;
+#[cfg(feature = "serde")]
+extern crate serde;
+// The unit test modules of the included files need their dependencies.
+#[cfg(test)]
+#[macro_use]
+extern crate proptest;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
+
+// The modules refer to each other with `super`, which works as long as
+// they are all siblings.
+#[path = "../../src/render/ansi.rs"]
+#[allow(dead_code)]
+mod ansi;
+#[path = "../../src/render/color.rs"]
+mod color;
+#[path = "../../src/render/color_names.rs"]
+#[allow(dead_code)]
+mod color_names;
+
+/// Lets the unit tests find `Color` at the same path as in way-cooler.
+mod render {
+    pub use color::Color;
+}
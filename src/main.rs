@@ -23,10 +23,19 @@ extern crate cairo;
 extern crate cairo_sys;
 extern crate gdk_pixbuf;
 extern crate glib;
+extern crate pango;
+extern crate pangocairo;
 #[macro_use]
 extern crate wayland_sys;
 extern crate wayland_server;
 extern crate xcb;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
+#[cfg(test)]
+#[macro_use]
+extern crate proptest;
 
 #[macro_use]
 mod macros;
@@ -0,0 +1,97 @@
+//! Chaining several transforms of a `Color`, eg for the colors of a theme
+//! that are derived from one base color.
+#![allow(dead_code)] // No theme derives its colors from a base color yet
+
+use super::color::Color;
+
+/// Applies transforms to a color one after the other, started with `Color::adjust`:
+///
+/// ```ignore
+/// let highlight = base.adjust().lighten(0.2).rotate_hue(30.0).with_alpha(128).build();
+/// ```
+///
+/// Every step is the method of `Color` with the same name, applied to the
+/// result of the steps before it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ColorAdjust {
+    color: Color
+}
+
+impl Color {
+    /// Starts adjusting the color with a `ColorAdjust`.
+    pub fn adjust(&self) -> ColorAdjust {
+        ColorAdjust { color: *self }
+    }
+}
+
+impl ColorAdjust {
+    /// See `Color::lighten`.
+    pub fn lighten(self, amount: f32) -> Self {
+        ColorAdjust { color: self.color.lighten(amount) }
+    }
+
+    /// See `Color::darken`.
+    pub fn darken(self, amount: f32) -> Self {
+        ColorAdjust { color: self.color.darken(amount) }
+    }
+
+    /// See `Color::saturate`.
+    pub fn saturate(self, amount: f32) -> Self {
+        ColorAdjust { color: self.color.saturate(amount) }
+    }
+
+    /// See `Color::desaturate`.
+    pub fn desaturate(self, amount: f32) -> Self {
+        ColorAdjust { color: self.color.desaturate(amount) }
+    }
+
+    /// See `Color::rotate_hue`.
+    pub fn rotate_hue(self, degrees: f32) -> Self {
+        ColorAdjust { color: self.color.rotate_hue(degrees) }
+    }
+
+    /// See `Color::with_alpha`.
+    pub fn with_alpha(self, alpha: u8) -> Self {
+        ColorAdjust { color: self.color.with_alpha(alpha) }
+    }
+
+    /// See `Color::scale_alpha`.
+    pub fn scale_alpha(self, factor: f32) -> Self {
+        ColorAdjust { color: self.color.scale_alpha(factor) }
+    }
+
+    /// See `Color::grayscale`.
+    pub fn grayscale(self) -> Self {
+        ColorAdjust { color: self.color.grayscale() }
+    }
+
+    /// See `Color::invert`.
+    pub fn invert(self) -> Self {
+        ColorAdjust { color: self.color.invert() }
+    }
+
+    /// Finishes adjusting, returning the color after all the steps.
+    pub fn build(self) -> Color {
+        self.color
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn chained() {
+        let color = Color::rgba(200, 100, 50, 255);
+        assert_eq!(color, color.adjust().build());
+        assert_eq!(color.lighten(0.2).rotate_hue(120.0),
+                   color.adjust().lighten(0.2).rotate_hue(120.0).build());
+        assert_eq!(color.darken(0.1).scale_alpha(0.5).desaturate(0.3),
+                   color.adjust().darken(0.1).scale_alpha(0.5).desaturate(0.3).build());
+        assert_eq!(Color::rgba(0, 255, 255, 128),
+                   Color::RED.adjust().invert().with_alpha(128).build());
+        // the order of the steps matters
+        assert_ne!(color.adjust().grayscale().saturate(0.5).build(),
+                   color.adjust().saturate(0.5).grayscale().build());
+    }
+}
@@ -0,0 +1,31 @@
+//! The 16 standard colors of terminals, for widgets that mirror them.
+
+use super::color::Color;
+
+/// The 16 ANSI colors as `0xRRGGBB`, indexed by their color number.
+///
+/// The first 8 are the normal colors (black, red, green, yellow, blue,
+/// magenta, cyan and white), followed by their bright variants.
+/// The values are the defaults of xterm.
+pub const ANSI_COLORS: [u32; 16] = [
+    0x000000, 0xCD0000, 0x00CD00, 0xCDCD00,
+    0x0000EE, 0xCD00CD, 0x00CDCD, 0xE5E5E5,
+    0x7F7F7F, 0xFF0000, 0x00FF00, 0xFFFF00,
+    0x5C5CFF, 0xFF00FF, 0x00FFFF, 0xFFFFFF
+];
+
+/// Gets the number of the ANSI color that looks the most like `color`.
+///
+/// If two ANSI colors are equally close, the lower number wins.
+pub fn nearest(color: &Color) -> u8 {
+    let mut nearest = 0;
+    let mut nearest_distance = f32::INFINITY;
+    for (index, &value) in ANSI_COLORS.iter().enumerate() {
+        let distance = color.distance(&Color::from(value));
+        if distance < nearest_distance {
+            nearest = index as u8;
+            nearest_distance = distance;
+        }
+    }
+    nearest
+}
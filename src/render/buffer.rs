@@ -0,0 +1,461 @@
+//! A Cairo buffer that can be drawn on with `Color`s pixel by pixel.
+#![allow(dead_code)] // Everything is still drawn with `BaseDraw`
+
+use std::f64::consts::{FRAC_PI_2, PI};
+
+use cairo::{self, BorrowError, Context, Format, ImageSurface};
+
+use super::color::Color;
+use super::pixel::{Pixels, BYTES_PER_PIXEL};
+
+/// The thresholds of ordered dithering, which spread the rounding errors
+/// evenly over every 4x4 block of pixels.
+const BAYER_4X4: [[u8; 4]; 4] = [[0, 8, 2, 10],
+                                 [12, 4, 14, 6],
+                                 [3, 11, 1, 9],
+                                 [15, 7, 13, 5]];
+
+/// The ways filling a `Buffer` with a checkerboard can go wrong.
+#[derive(Debug)]
+pub enum CheckerboardError {
+    /// The cells of a checkerboard can't be 0 pixels big.
+    ZeroCellSize,
+    /// The data of the surface is borrowed by something else.
+    Borrow(BorrowError)
+}
+
+impl From<BorrowError> for CheckerboardError {
+    fn from(err: BorrowError) -> Self {
+        CheckerboardError::Borrow(err)
+    }
+}
+
+/// Wraps a Cairo `ImageSurface` in the `ARgb32` format, to draw
+/// colored shapes directly into its pixels.
+///
+/// The data of the surface can only be accessed while no Cairo `Context`
+/// is drawing on it, otherwise the drawing methods return a `BorrowError`.
+pub struct Buffer {
+    surface: ImageSurface
+}
+
+impl Buffer {
+    /// Makes a new, fully transparent buffer of the given size.
+    pub fn new(width: u32, height: u32) -> Result<Self, cairo::Status> {
+        let surface = ImageSurface::create(Format::ARgb32, width as i32, height as i32)?;
+        Ok(Buffer { surface })
+    }
+
+    /// Makes a new, fully transparent buffer whose rows are `stride` bytes
+    /// apart, which may be more than 4 bytes per pixel.
+    pub fn with_stride(width: u32, height: u32, stride: u32) -> Result<Self, cairo::Status> {
+        let data = vec![0; height as usize * stride as usize].into_boxed_slice();
+        let surface = ImageSurface::create_for_data(data, |_| {}, Format::ARgb32,
+                                                    width as i32, height as i32,
+                                                    stride as i32)?;
+        Ok(Buffer { surface })
+    }
+
+    /// Gets the width of the buffer in pixels.
+    pub fn width(&self) -> u32 {
+        self.surface.get_width() as u32
+    }
+
+    /// Gets the height of the buffer in pixels.
+    pub fn height(&self) -> u32 {
+        self.surface.get_height() as u32
+    }
+
+    /// Gets the underlying surface, eg to draw on it with a Cairo `Context`.
+    pub fn get_surface(&mut self) -> &mut ImageSurface {
+        &mut self.surface
+    }
+
+    /// Unwraps the underlying surface.
+    pub fn into_surface(self) -> ImageSurface {
+        self.surface
+    }
+
+    /// Fills the rectangle with the color, replacing what was there before.
+    ///
+    /// The parts of the rectangle outside of the buffer are clipped.
+    pub fn fill_rect(&mut self, x: i32, y: i32, w: u32, h: u32, color: Color)
+                     -> Result<(), BorrowError> {
+        let (width, height) = (self.width() as i64, self.height() as i64);
+        let x_start = (x as i64).max(0).min(width) as usize;
+        let x_end = (x as i64 + w as i64).max(0).min(width) as usize;
+        let y_start = (y as i64).max(0).min(height) as usize;
+        let y_end = (y as i64 + h as i64).max(0).min(height) as usize;
+        if x_start == x_end || y_start == y_end {
+            return Ok(())
+        }
+        let pixel = color.as_cairo_argb32().to_ne_bytes();
+        let stride = self.surface.get_stride() as usize;
+        let mut data = self.surface.get_data()?;
+        for row in y_start..y_end {
+            let row_start = row * stride;
+            let row_data = &mut data[row_start + x_start * BYTES_PER_PIXEL..
+                                     row_start + x_end * BYTES_PER_PIXEL];
+            for dest in row_data.chunks_mut(BYTES_PER_PIXEL) {
+                dest.copy_from_slice(&pixel);
+            }
+        }
+        Ok(())
+    }
+
+    /// Draws a rectangle with rounded corners over what was there before,
+    /// with Cairo's antialiasing.
+    ///
+    /// The radii of the corners are in the order top left, top right,
+    /// bottom right, bottom left (like in CSS). They are clamped to half of
+    /// the smaller side of the rectangle, so two corners never overlap.
+    pub fn draw_rounded_rect(&mut self, x: i32, y: i32, w: u32, h: u32,
+                             radii: [u32; 4], fill: Color) -> Result<(), cairo::Status> {
+        let max_radius = w.min(h) as f64 / 2.0;
+        let [top_left, top_right, bottom_right, bottom_left] =
+            radii.map(|radius| (radius as f64).min(max_radius));
+        let (left, top) = (x as f64, y as f64);
+        let (right, bottom) = (left + w as f64, top + h as f64);
+        let context = Context::new(&self.surface);
+        context.new_sub_path();
+        context.arc(right - top_right, top + top_right, top_right, -FRAC_PI_2, 0.0);
+        context.arc(right - bottom_right, bottom - bottom_right, bottom_right, 0.0, FRAC_PI_2);
+        context.arc(left + bottom_left, bottom - bottom_left, bottom_left, FRAC_PI_2, PI);
+        context.arc(left + top_left, top + top_left, top_left, PI, 3.0 * FRAC_PI_2);
+        context.close_path();
+        let (red, green, blue, alpha) = fill.to_cairo_rgba();
+        context.set_source_rgba(red, green, blue, alpha);
+        context.fill();
+        match context.status() {
+            cairo::Status::Success => Ok(()),
+            err => Err(err)
+        }
+    }
+
+    /// Fills the whole buffer with a checkerboard of square cells that are
+    /// `cell` pixels big, eg to see the transparency of what is drawn over it.
+    ///
+    /// The cell in the top left corner is `light`, its neighbours are `dark`.
+    pub fn fill_checkerboard(&mut self, cell: u32, light: Color, dark: Color)
+                             -> Result<(), CheckerboardError> {
+        if cell == 0 {
+            return Err(CheckerboardError::ZeroCellSize)
+        }
+        for (x, y, mut pixel) in self.pixels_mut()?.iter_mut() {
+            if (x / cell + y / cell) % 2 == 0 {
+                pixel.set(light);
+            } else {
+                pixel.set(dark);
+            }
+        }
+        Ok(())
+    }
+
+    /// Reduces every pixel to `levels` values per channel, like
+    /// `Color::quantize`, with ordered (Bayer) dithering.
+    ///
+    /// Before a pixel is quantized, an offset of up to half a level is added
+    /// to it, which depends on its position in a 4x4 pattern. So smooth
+    /// gradients turn into a pattern of the levels instead of flat bands.
+    /// The alpha channel is kept.
+    pub fn dither_ordered(&mut self, levels: u8) -> Result<(), BorrowError> {
+        let step = 255.0 / (levels.max(2) - 1) as f32;
+        for (x, y, mut pixel) in self.pixels_mut()?.iter_mut() {
+            let threshold = BAYER_4X4[y as usize % 4][x as usize % 4];
+            let offset = ((threshold as f32 + 0.5) / 16.0 - 0.5) * step;
+            let color = pixel.get();
+            let dithered = Color::clamp_to_gamut(color.red() as f32 + offset,
+                                                 color.green() as f32 + offset,
+                                                 color.blue() as f32 + offset,
+                                                 color.alpha() as f32);
+            pixel.set(dithered.quantize(levels));
+        }
+        Ok(())
+    }
+
+    /// Borrows the pixels, to read and write them as `Color`s.
+    pub fn pixels_mut<'a>(&'a mut self) -> Result<Pixels<'a>, BorrowError> {
+        let width = self.width() as usize;
+        let stride = self.surface.get_stride() as usize;
+        Ok(Pixels::new(self.surface.get_data()?, width, stride))
+    }
+
+    /// Reads the packed, premultiplied pixel at the position, in the format
+    /// `Color::as_cairo_argb32` produces.
+    ///
+    /// Returns `Ok(None)` if the position is outside of the buffer.
+    pub fn pixel(&mut self, x: u32, y: u32) -> Result<Option<u32>, BorrowError> {
+        if x >= self.width() || y >= self.height() {
+            return Ok(None)
+        }
+        let index = y as usize * self.surface.get_stride() as usize
+            + x as usize * BYTES_PER_PIXEL;
+        let data = self.surface.get_data()?;
+        let mut bytes = [0; BYTES_PER_PIXEL];
+        bytes.copy_from_slice(&data[index..index + BYTES_PER_PIXEL]);
+        Ok(Some(u32::from_ne_bytes(bytes)))
+    }
+}
+
+/// Draws a border of the given thickness along the edges of the buffer,
+/// leaving the inside untouched.
+///
+/// If the border is thicker than half of the buffer, the whole buffer is filled.
+pub fn draw_border(buffer: &mut Buffer, thickness: u32, color: Color)
+                   -> Result<(), BorrowError> {
+    let (width, height) = (buffer.width(), buffer.height());
+    if thickness == 0 {
+        return Ok(())
+    }
+    let double_thickness = 2 * thickness as u64;
+    if double_thickness >= width as u64 || double_thickness >= height as u64 {
+        return buffer.fill_rect(0, 0, width, height, color)
+    }
+    let inner_height = height - 2 * thickness;
+    // top, bottom, left, right
+    buffer.fill_rect(0, 0, width, thickness, color)?;
+    buffer.fill_rect(0, (height - thickness) as i32, width, thickness, color)?;
+    buffer.fill_rect(0, thickness as i32, thickness, inner_height, color)?;
+    buffer.fill_rect((width - thickness) as i32, thickness as i32,
+                     thickness, inner_height, color)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Reads all the pixels of the buffer, row by row.
+    pub fn pixels(buffer: &mut Buffer) -> Vec<Vec<u32>> {
+        (0..buffer.height()).map(|y| {
+            (0..buffer.width())
+                .map(|x| buffer.pixel(x, y).unwrap().unwrap())
+                .collect()
+        }).collect()
+    }
+
+    #[test]
+    fn new() {
+        let mut buffer = Buffer::new(3, 2).unwrap();
+        assert_eq!(3, buffer.width());
+        assert_eq!(2, buffer.height());
+        assert_eq!(vec![vec![0, 0, 0], vec![0, 0, 0]], pixels(&mut buffer));
+        assert_eq!(None, buffer.pixel(3, 0).unwrap());
+        assert_eq!(None, buffer.pixel(0, 2).unwrap());
+    }
+
+    #[test]
+    fn fill_rect() {
+        let red = Color::rgba(255, 0, 0, 255);
+        let half_blue = Color::rgba(0, 0, 255, 128);
+        let mut buffer = Buffer::new(4, 3).unwrap();
+        buffer.fill_rect(1, 1, 2, 2, red).unwrap();
+        let r = red.as_cairo_argb32();
+        assert_eq!(0xFFFF0000, r);
+        assert_eq!(vec![vec![0, 0, 0, 0],
+                        vec![0, r, r, 0],
+                        vec![0, r, r, 0]],
+                   pixels(&mut buffer));
+        // filling replaces the pixels instead of blending
+        buffer.fill_rect(0, 0, 2, 2, half_blue).unwrap();
+        let b = half_blue.as_cairo_argb32();
+        assert_eq!(vec![vec![b, b, 0, 0],
+                        vec![b, b, r, 0],
+                        vec![0, r, r, 0]],
+                   pixels(&mut buffer));
+    }
+
+    #[test]
+    fn fill_rect_clipped() {
+        let red = Color::rgba(255, 0, 0, 255);
+        let r = red.as_cairo_argb32();
+        let mut buffer = Buffer::new(3, 3).unwrap();
+        buffer.fill_rect(-2, -2, 3, 3, red).unwrap();
+        buffer.fill_rect(2, 2, 100, 100, red).unwrap();
+        // completely outside or empty
+        buffer.fill_rect(-10, 0, 5, 5, red).unwrap();
+        buffer.fill_rect(3, 0, 5, 5, red).unwrap();
+        buffer.fill_rect(1, 1, 0, 5, red).unwrap();
+        buffer.fill_rect(i32::MAX, i32::MAX, u32::MAX, u32::MAX, red).unwrap();
+        assert_eq!(vec![vec![r, 0, 0],
+                        vec![0, 0, 0],
+                        vec![0, 0, r]],
+                   pixels(&mut buffer));
+        buffer.fill_rect(i32::MIN, i32::MIN, u32::MAX, u32::MAX, red).unwrap();
+        assert_eq!(vec![vec![r, r, r], vec![r, r, r], vec![r, r, r]],
+                   pixels(&mut buffer));
+    }
+
+    #[test]
+    fn pixels_mut() {
+        let red = Color::rgba(255, 0, 0, 255);
+        let tinted = Color::rgba(255, 0, 128, 255);
+        let blue = Color::rgba(0, 0, 255, 255);
+        // 3 pixels take up 12 bytes, so every row has 8 bytes of padding
+        for (mut buffer, stride) in [(Buffer::new(3, 2).unwrap(), 12),
+                                     (Buffer::with_stride(3, 2, 20).unwrap(), 20)] {
+            assert_eq!(stride, buffer.get_surface().get_stride());
+            buffer.fill_rect(0, 0, 3, 1, red).unwrap();
+            let mut positions = Vec::new();
+            for (x, y, mut pixel) in buffer.pixels_mut().unwrap().iter_mut() {
+                positions.push((x, y));
+                let color = pixel.get();
+                if color == red {
+                    pixel.set(color.blend_screen(&Color::rgba(0, 0, 128, 255)));
+                } else {
+                    assert_eq!(Color::TRANSPARENT, color);
+                    pixel.set(blue);
+                }
+            }
+            assert_eq!(vec![(0, 0), (1, 0), (2, 0), (0, 1), (1, 1), (2, 1)], positions);
+            let (t, b) = (tinted.as_cairo_argb32(), blue.as_cairo_argb32());
+            assert_eq!(vec![vec![t, t, t], vec![b, b, b]], pixels(&mut buffer));
+        }
+    }
+
+    #[test]
+    fn pixels_mut_padding() {
+        let mut buffer = Buffer::with_stride(1, 2, 8).unwrap();
+        for (_, _, mut pixel) in buffer.pixels_mut().unwrap().iter_mut() {
+            pixel.set(Color::WHITE);
+        }
+        // the padding is left alone
+        let data = buffer.get_surface().get_data().unwrap().to_vec();
+        assert_eq!(vec![255, 255, 255, 255, 0, 0, 0, 0,
+                        255, 255, 255, 255, 0, 0, 0, 0], data);
+    }
+
+    #[test]
+    fn draw_rounded_rect() {
+        let red = Color::rgba(255, 0, 0, 255);
+        let r = red.as_cairo_argb32();
+        let mut buffer = Buffer::new(14, 12).unwrap();
+        buffer.draw_rounded_rect(1, 1, 12, 10, [4, 4, 4, 4], red).unwrap();
+        let drawn = pixels(&mut buffer);
+        // the corners are cut off
+        for &(x, y) in &[(1, 1), (12, 1), (12, 10), (1, 10)] {
+            assert_eq!(0, drawn[y][x], "({}, {})", x, y);
+        }
+        // but the centers of the edges and the middle are filled
+        for &(x, y) in &[(6, 1), (12, 5), (6, 10), (1, 5), (6, 5)] {
+            assert_eq!(r, drawn[y][x], "({}, {})", x, y);
+        }
+        // outside of the rectangle is untouched
+        assert!(drawn[0].iter().all(|&pixel| pixel == 0));
+        assert!(drawn.iter().all(|row| row[0] == 0 && row[13] == 0));
+    }
+
+    #[test]
+    fn draw_rounded_rect_radii() {
+        let red = Color::rgba(255, 0, 0, 255);
+        let r = red.as_cairo_argb32();
+        // only the top right and bottom left corners are rounded
+        let mut buffer = Buffer::new(8, 8).unwrap();
+        buffer.draw_rounded_rect(0, 0, 8, 8, [0, 4, 0, 4], red).unwrap();
+        let drawn = pixels(&mut buffer);
+        assert_eq!((r, 0, r, 0), (drawn[0][0], drawn[0][7], drawn[7][7], drawn[7][0]));
+        // huge radii are clamped, so this is a circle
+        let mut buffer = Buffer::new(8, 8).unwrap();
+        buffer.draw_rounded_rect(0, 0, 8, 8, [100; 4], red).unwrap();
+        let drawn = pixels(&mut buffer);
+        assert_eq!((0, 0, 0, 0), (drawn[0][0], drawn[0][7], drawn[7][7], drawn[7][0]));
+        assert_eq!((r, r, r, r), (drawn[1][4], drawn[4][6], drawn[6][4], drawn[4][1]));
+        assert_eq!(r, drawn[4][4]);
+    }
+
+    #[test]
+    fn fill_checkerboard() {
+        let (light, dark) = (Color::rgba(204, 204, 204, 255), Color::rgba(102, 102, 102, 255));
+        let (l, d) = (light.as_cairo_argb32(), dark.as_cairo_argb32());
+        let mut buffer = Buffer::new(5, 3).unwrap();
+        buffer.fill_checkerboard(2, light, dark).unwrap();
+        assert_eq!(vec![vec![l, l, d, d, l],
+                        vec![l, l, d, d, l],
+                        vec![d, d, l, l, d]],
+                   pixels(&mut buffer));
+        assert_ne!(buffer.pixel(0, 0).unwrap(), buffer.pixel(2, 0).unwrap());
+        buffer.fill_checkerboard(1, light, dark).unwrap();
+        assert_eq!(vec![vec![l, d, l, d, l],
+                        vec![d, l, d, l, d],
+                        vec![l, d, l, d, l]],
+                   pixels(&mut buffer));
+        // cells bigger than the buffer
+        buffer.fill_checkerboard(10, light, dark).unwrap();
+        assert_eq!(vec![vec![l; 5]; 3], pixels(&mut buffer));
+        match buffer.fill_checkerboard(0, light, dark) {
+            Err(CheckerboardError::ZeroCellSize) => {},
+            result => panic!("{:?}", result)
+        }
+    }
+
+    #[test]
+    fn dither_ordered() {
+        // a horizontal gradient from black to white
+        let mut buffer = Buffer::new(64, 4).unwrap();
+        for (x, _, mut pixel) in buffer.pixels_mut().unwrap().iter_mut() {
+            let gray = (x * 255 / 63) as u8;
+            pixel.set(Color::rgba(gray, gray, gray, 255));
+        }
+        buffer.dither_ordered(2).unwrap();
+        let (black, white) = (Color::BLACK.as_cairo_argb32(), Color::WHITE.as_cairo_argb32());
+        let dithered = pixels(&mut buffer);
+        assert!(dithered.iter().flatten().all(|&pixel| pixel == black || pixel == white));
+        // the ends stay as they were
+        assert!(dithered.iter().all(|row| row[0] == black && row[63] == white));
+        // but in between, the levels are mixed instead of having one edge
+        let count_white = |x: usize| dithered.iter().filter(|row| row[x] == white).count();
+        let whites: Vec<usize> = (0..64).map(count_white).collect();
+        assert!(whites.iter().any(|&count| count != 0 && count != 4), "{:?}", whites);
+        for row in &dithered {
+            let edges = row.windows(2).filter(|pair| pair[0] != pair[1]).count();
+            assert!(edges > 1, "{:?}", row);
+        }
+        // and get brighter towards the right
+        let left: usize = whites[..32].iter().sum();
+        let right: usize = whites[32..].iter().sum();
+        assert!(left < right, "{:?}", whites);
+    }
+
+    #[test]
+    fn draw_border() {
+        let red = Color::rgba(255, 0, 0, 255);
+        let r = red.as_cairo_argb32();
+        let mut buffer = Buffer::new(5, 4).unwrap();
+        super::draw_border(&mut buffer, 1, red).unwrap();
+        assert_eq!(vec![vec![r, r, r, r, r],
+                        vec![r, 0, 0, 0, r],
+                        vec![r, 0, 0, 0, r],
+                        vec![r, r, r, r, r]],
+                   pixels(&mut buffer));
+        let mut buffer = Buffer::new(6, 6).unwrap();
+        super::draw_border(&mut buffer, 2, red).unwrap();
+        assert_eq!(vec![vec![r, r, r, r, r, r],
+                        vec![r, r, r, r, r, r],
+                        vec![r, r, 0, 0, r, r],
+                        vec![r, r, 0, 0, r, r],
+                        vec![r, r, r, r, r, r],
+                        vec![r, r, r, r, r, r]],
+                   pixels(&mut buffer));
+    }
+
+    #[test]
+    fn draw_border_edge_cases() {
+        let red = Color::rgba(255, 0, 0, 255);
+        let r = red.as_cairo_argb32();
+        // no border
+        let mut buffer = Buffer::new(3, 3).unwrap();
+        super::draw_border(&mut buffer, 0, red).unwrap();
+        assert_eq!(vec![vec![0, 0, 0], vec![0, 0, 0], vec![0, 0, 0]],
+                   pixels(&mut buffer));
+        // thicker than half the buffer
+        super::draw_border(&mut buffer, 2, red).unwrap();
+        assert_eq!(vec![vec![r, r, r], vec![r, r, r], vec![r, r, r]],
+                   pixels(&mut buffer));
+        let mut buffer = Buffer::new(8, 2).unwrap();
+        super::draw_border(&mut buffer, 1, red).unwrap();
+        assert_eq!(vec![vec![r; 8], vec![r; 8]], pixels(&mut buffer));
+        let mut buffer = Buffer::new(3, 3).unwrap();
+        super::draw_border(&mut buffer, 1000, red).unwrap();
+        assert_eq!(vec![vec![r, r, r], vec![r, r, r], vec![r, r, r]],
+                   pixels(&mut buffer));
+    }
+}
@@ -1,11 +1,224 @@
 //! Colors used for drawing to a Cairo buffer
+#![allow(dead_code)] // way-cooler only parses colors with it so far
 
-use std::convert::From;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::convert::{From, TryFrom};
+use std::error::Error;
+use std::fmt;
+use std::ops::{Add, Mul, Sub};
+use std::str::FromStr;
+
+use super::ansi;
+use super::color_names;
+
+/// The prefix to put in front of a serialized hex color.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum HexPrefix {
+    /// No prefix, eg "RRGGBB"
+    None,
+    /// HTML-style prefix, eg "#RRGGBB"
+    Hash,
+    /// Hex-style prefix, eg "0xRRGGBB"
+    ZeroX
+}
+
+/// Which formats `Color::parse_with` accepts.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum ParseMode {
+    /// Only the canonical formats "#AARRGGBB" and "#RRGGBB",
+    /// without any whitespace around them.
+    Strict,
+    /// Every format `Color::parse` understands.
+    Lenient
+}
+
+/// Where the alpha channel is in the 8 (and 16) digit hex formats,
+/// for `Color::parse_with_order`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum HexOrder {
+    /// The alpha channel comes first, eg "#AARRGGBB". This is what `parse` does.
+    Argb,
+    /// The alpha channel comes last, eg "#RRGGBBAA", like in CSS.
+    Rgba
+}
+
+/// How many strings `Color::parse_cached` remembers per thread. When the
+/// cache is full it is emptied, which is good enough for reloading a config.
+const PARSE_CACHE_CAPACITY: usize = 256;
+
+thread_local! {
+    /// The results of `Color::parse_cached`, by the string that was parsed.
+    static PARSE_CACHE: RefCell<HashMap<String, Option<Color>>> = RefCell::new(HashMap::new());
+}
+
+/// The reasons parsing a `Color` from a string can fail.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum ColorParseError {
+    /// The string was empty.
+    EmptyInput,
+    /// After removing the prefix, the string didn't have a supported length.
+    InvalidLength { got: usize },
+    /// A character that is not a hex digit was found at the (character) position.
+    InvalidDigit { position: usize, found: char },
+    /// A functional notation (eg "rgb(...)"), an X11 "rgb:" color or
+    /// a list of channels (eg "255,0,0") was malformed or out of range.
+    InvalidFunction,
+    /// The color at the index of a list of colors could not be parsed.
+    InvalidEntry { index: usize, reason: Box<ColorParseError> },
+    /// The string is not a known color name.
+    UnknownName
+}
+
+impl fmt::Display for ColorParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ColorParseError::EmptyInput =>
+                write!(f, "the color is empty"),
+            ColorParseError::InvalidLength { got } =>
+                write!(f, "the color has {} digits, expected 3, 4, 6, 8, 12 or 16", got),
+            ColorParseError::InvalidDigit { position, found } =>
+                write!(f, "the color has an invalid hex digit '{}' at position {}",
+                       found, position),
+            ColorParseError::InvalidFunction =>
+                write!(f, "the color function is malformed"),
+            ColorParseError::InvalidEntry { index, ref reason } =>
+                write!(f, "color number {} is invalid: {}", index, reason),
+            ColorParseError::UnknownName =>
+                write!(f, "the color name is unknown")
+        }
+    }
+}
+
+impl Error for ColorParseError {}
+
+/// The error when converting bytes to a `Color` that are neither
+/// 3 nor 4 bytes long.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct InvalidPixelLength {
+    pub got: usize
+}
+
+impl fmt::Display for InvalidPixelLength {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the pixel has {} bytes, expected 3 or 4", self.got)
+    }
+}
+
+impl Error for InvalidPixelLength {}
+
+/// A color packed once into a pixel of a `Format::ARgb32` surface, to fill
+/// many pixels with it. Made with `Color::mul_alpha_into_u32`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct PackedColor(u32);
+
+impl PackedColor {
+    /// The packed pixel, the same as `Color::as_cairo_argb32`.
+    pub fn get(self) -> u32 {
+        self.0
+    }
+
+    /// Sets every pixel of `row` to the color.
+    pub fn fill_row(self, row: &mut [u32]) {
+        for pixel in row {
+            *pixel = self.0;
+        }
+    }
+}
+
+/// The space colors are mixed in by the blending methods of `Color`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum GammaMode {
+    /// Mix the sRGB encoded values directly. This is fast, but the
+    /// mixed colors are too dark (eg black and white mix to a dark gray).
+    Srgb,
+    /// Convert the colors into linear light before mixing them, and back
+    /// into sRGB afterwards. Slower, but the mixed colors are more accurate.
+    Linear
+}
+
+impl GammaMode {
+    /// Converts a channel into a value from 0.0 to 1.0 that can be mixed.
+    fn decode(&self, channel: u8) -> f32 {
+        match *self {
+            GammaMode::Srgb => channel as f32 / 255.0,
+            GammaMode::Linear => Color::srgb_to_linear(channel as f32 / 255.0)
+        }
+    }
+
+    /// Converts a mixed value back into a channel.
+    fn encode(&self, value: f32) -> u8 {
+        match *self {
+            GammaMode::Srgb => Color::channel_from_unit(value),
+            GammaMode::Linear => Color::channel_from_unit(Color::linear_to_srgb(value))
+        }
+    }
+}
+
+/// Marks the bytes in `HEX_DIGITS` that are not hex digits.
+const INVALID_HEX_DIGIT: u8 = 0xFF;
+
+/// The value of every byte as a hex digit, which is much faster
+/// than `char::to_digit` when parsing lots of colors.
+static HEX_DIGITS: [u8; 256] = hex_digit_table();
+
+const fn hex_digit_table() -> [u8; 256] {
+    let mut table = [INVALID_HEX_DIGIT; 256];
+    let mut digit = 0;
+    while digit < 10 {
+        table[(b'0' + digit) as usize] = digit;
+        digit += 1;
+    }
+    let mut digit = 0;
+    while digit < 6 {
+        table[(b'a' + digit) as usize] = 10 + digit;
+        table[(b'A' + digit) as usize] = 10 + digit;
+        digit += 1;
+    }
+    table
+}
+
+/// Converts between the real channels of a color and the values stored in
+/// a `Color`, in both directions.
+///
+/// There is a bug in wlc, causing red and blue to be inverted:
+/// https://github.com/Cloudef/wlc/issues/142
+/// We can work around it, by just switching red with blue, until the issue is resolved.
+/// When the bug is fixed, build without the `wlc-color-swap` feature,
+/// which makes this the identity.
+///
+/// Switching red and blue twice gives the original channels, so the same
+/// function stores channels (in `Color::rgba`) and reads them back (in
+/// `Color::unswapped`). Everything else should go through those two
+/// instead of accessing the fields, so only this needs to know about the bug.
+#[cfg(feature = "wlc-color-swap")]
+const fn wlc_swap(red: u8, green: u8, blue: u8, alpha: u8) -> (u8, u8, u8, u8) {
+    (blue, green, red, alpha)
+}
+
+/// Converts between the real channels of a color and the values stored in
+/// a `Color`. Without the `wlc-color-swap` feature, they are the same.
+#[cfg(not(feature = "wlc-color-swap"))]
+const fn wlc_swap(red: u8, green: u8, blue: u8, alpha: u8) -> (u8, u8, u8, u8) {
+    (red, green, blue, alpha)
+}
 
 /// Color to draw to the screen, including the alpha channel.
-/// NOTE: At this point, the parsed colors return the colors red and blue switched.
+///
+/// NOTE: With the `wlc-color-swap` feature (enabled by default), the parsed colors
+/// return the colors red and blue switched.
 /// This is due to a bug in WLC, causing the colors to be switched when drawing.
 /// Example: "00FF0000" will draw as red (correct), but the Color structure will contain 0 for `red` and 255 for `blue`.
+///
+/// Which mode is needed depends on what the colors are drawn with:
+/// - wlc releases affected by https://github.com/Cloudef/wlc/issues/142 need the
+///   `wlc-color-swap` feature, otherwise red and blue are switched on the screen.
+/// - wlc releases with the issue fixed, and backends that draw the Cairo buffers
+///   themselves, need to be built with `--no-default-features`.
+///
+/// All the methods that work on single channels (everything except `values`)
+/// work on the real, unswapped channels in both modes.
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
 pub struct Color {
     red: u8,
@@ -16,200 +229,1942 @@ pub struct Color {
 
 
 impl Color {
+    /// Opaque black.
+    pub const BLACK: Color = Color::rgba(0, 0, 0, 255);
+    /// Opaque white.
+    pub const WHITE: Color = Color::rgba(255, 255, 255, 255);
+    /// Opaque red.
+    pub const RED: Color = Color::rgba(255, 0, 0, 255);
+    /// Opaque green.
+    pub const GREEN: Color = Color::rgba(0, 255, 0, 255);
+    /// Opaque blue.
+    pub const BLUE: Color = Color::rgba(0, 0, 255, 255);
+    /// Fully transparent black.
+    pub const TRANSPARENT: Color = Color::rgba(0, 0, 0, 0);
 
     /// Creates a new color with an alphachannel
-    pub fn rgba(r: u8, g: u8, b: u8, a: u8) -> Color {
-        // There is a bug in wlc, causing red and blue to be inverted:
-        // https://github.com/Cloudef/wlc/issues/142
-        // We can work around it, by just switching red with blue, until the issue is resolved.
-        // When the bug is fixed, this code path can be deleted and the tests must be adjusted.
-        Color {
-            red:   b,
-            green: g,
-            blue:  r,
-            alpha: a
-        }
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Color {
+        let (red, green, blue, alpha) = wlc_swap(r, g, b, a);
+        Color { red, green, blue, alpha }
+    }
+
+    /// Creates a color from a packed `0xAARRGGBB` value.
+    ///
+    /// Unlike `From<u32>`, which always produces an opaque color,
+    /// this takes the alpha channel from the highest byte.
+    pub fn from_argb_u32(val: u32) -> Color {
+        let alpha = ((val & 0xff000000) >> 24) as u8;
+        Color::from(val).with_alpha(alpha)
+    }
+
+    /// Packs the color into a `0xAARRGGBB` value, the inverse of `from_argb_u32`.
+    pub fn to_argb_u32(&self) -> u32 {
+        let (red, green, blue, alpha) = self.unswapped();
+        (alpha as u32) << 24 | (red as u32) << 16 | (green as u32) << 8 | blue as u32
+    }
+
+    /// Packs the color into 16 bits, with 5 bits of red, 6 bits of green
+    /// and 5 bits of blue (from the highest to the lowest bits).
+    ///
+    /// The channels are rounded to the nearest value, the alpha channel is dropped.
+    pub fn to_rgb565(&self) -> u16 {
+        let (red, green, blue, _) = self.unswapped();
+        let reduce = |channel: u8, max: u16| (channel as u16 * max + 127) / 255;
+        reduce(red, 31) << 11 | reduce(green, 63) << 5 | reduce(blue, 31)
+    }
+
+    /// Unpacks an opaque color from the format of `to_rgb565`.
+    ///
+    /// The high bits of the channels are repeated in the low bits, so
+    /// eg `0b11111` becomes 255 and `0b10000` becomes 132.
+    pub fn from_rgb565(val: u16) -> Color {
+        let red = (val >> 11) as u8;
+        let green = (val >> 5 & 0x3F) as u8;
+        let blue = (val & 0x1F) as u8;
+        Color::rgba(red << 3 | red >> 2, green << 2 | green >> 4, blue << 3 | blue >> 2, 255)
     }
 
     /// Gets the values of the colors, in this order:
     /// (Red, Green, Blue, Alpha)
+    ///
+    /// These are the stored values, so with the `wlc-color-swap` feature
+    /// red and blue are switched. This is what needs to be drawn with wlc.
+    /// Use `red`, `green`, `blue` and `alpha` to get the real channels.
     pub fn values(&self) -> (u8, u8, u8, u8) {
         (self.red, self.green, self.blue, self.alpha)
     }
 
-    /// Parses a String into a Color
-    /// The following formats are supported:
-    /// - "RRGGBB"
-    /// - "AARRGGBB"
-    /// - "#RRGGBB"
-    /// - "#AARRGGBB"
-    /// - "0xRRGGBB"
-    /// - "0xAARRGGBB"
-    pub fn parse(s: &str) -> Option<Color> {
-        if s.starts_with("#") {
-            let (_, sub) = s.split_at(1);
-            Color::parse(sub)
-        } else if s.starts_with("0x") {
-            let (_, sub) = s.split_at(2);
-            Color::parse(sub)
-        } else if s.len() == 8 {
-            Color::parse_argb(s)
-        } else if s.len() == 6 {
-            Color::parse_rgb(s)
+    /// Gets the red channel.
+    ///
+    /// Unlike `values`, this is always the real red channel, even if
+    /// red and blue are stored switched.
+    pub fn red(&self) -> u8 {
+        self.unswapped().0
+    }
+
+    /// Gets the green channel.
+    pub fn green(&self) -> u8 {
+        self.green
+    }
+
+    /// Gets the blue channel.
+    ///
+    /// Unlike `values`, this is always the real blue channel, even if
+    /// red and blue are stored switched.
+    pub fn blue(&self) -> u8 {
+        self.unswapped().2
+    }
+
+    /// Gets the alpha channel.
+    pub fn alpha(&self) -> u8 {
+        self.alpha
+    }
+
+    /// Gets the real channels in the order red, green, blue, alpha, eg to
+    /// process all of them the same way with `map`.
+    pub fn channels(&self) -> [u8; 4] {
+        let (red, green, blue, alpha) = self.unswapped();
+        [red, green, blue, alpha]
+    }
+
+    /// Creates a color from its channels in the order red, green, blue,
+    /// alpha, the inverse of `channels`.
+    pub fn from_channels(channels: [u8; 4]) -> Color {
+        let [red, green, blue, alpha] = channels;
+        Color::rgba(red, green, blue, alpha)
+    }
+
+    /// Gets the red channel, from 0.0 to 1.0.
+    pub fn red_f32(&self) -> f32 {
+        self.red() as f32 / 255.0
+    }
+
+    /// Gets the green channel, from 0.0 to 1.0.
+    pub fn green_f32(&self) -> f32 {
+        self.green() as f32 / 255.0
+    }
+
+    /// Gets the blue channel, from 0.0 to 1.0.
+    pub fn blue_f32(&self) -> f32 {
+        self.blue() as f32 / 255.0
+    }
+
+    /// Gets the alpha channel, from 0.0 to 1.0.
+    pub fn alpha_f32(&self) -> f32 {
+        self.alpha as f32 / 255.0
+    }
+
+    /// Gets the real (unswapped) channels from 0.0 to 1.0, in the order
+    /// `cairo::Context::set_source_rgba` takes them.
+    pub fn to_cairo_rgba(&self) -> (f64, f64, f64, f64) {
+        let (red, green, blue, alpha) = self.unswapped();
+        (red as f64 / 255.0, green as f64 / 255.0, blue as f64 / 255.0, alpha as f64 / 255.0)
+    }
+
+    /// Checks if the color is fully opaque, so it completely covers
+    /// whatever it is drawn over.
+    pub fn is_opaque(&self) -> bool {
+        self.alpha == 255
+    }
+
+    /// Checks if the color is fully transparent, so drawing it has no effect.
+    pub fn is_transparent(&self) -> bool {
+        self.alpha == 0
+    }
+
+    /// Checks if the color is a neutral gray (including black and white),
+    /// so its red, green and blue channels are the same. The alpha channel is ignored.
+    pub fn is_grayscale(&self) -> bool {
+        self.red == self.green && self.green == self.blue
+    }
+
+    /// Checks if the two colors have the same real (unswapped) channels.
+    ///
+    /// The derived `PartialEq` compares the stored fields instead. As every
+    /// `Color` is created by swapping its channels the same way (see
+    /// `wlc_swap`), both always agree. This doesn't rely on that, so it is
+    /// the comparison to use for colors that come from outside, eg raw pixels.
+    pub fn logical_eq(&self, other: &Color) -> bool {
+        self.unswapped() == other.unswapped()
+    }
+
+    /// Gets the real values of the colors, undoing the switch of red and
+    /// blue done by `rgba`, in this order: (Red, Green, Blue, Alpha)
+    fn unswapped(&self) -> (u8, u8, u8, u8) {
+        wlc_swap(self.red, self.green, self.blue, self.alpha)
+    }
+
+    /// Creates a color from channels that are the result of a calculation,
+    /// so they may be out of the range 0.0-255.0 or not integers.
+    ///
+    /// Every channel is rounded and clamped the same way all the operations
+    /// of `Color` do it: to the nearest integer, with halves rounded to the
+    /// even integer, then clamped to 0-255. Channels that are already valid
+    /// (integers in 0-255) are kept as they are.
+    pub fn clamp_to_gamut(red: f32, green: f32, blue: f32, alpha: f32) -> Color {
+        Color::rgba(Color::clamp_channel(red),
+                    Color::clamp_channel(green),
+                    Color::clamp_channel(blue),
+                    Color::clamp_channel(alpha))
+    }
+
+    /// Gets a copy of the color with the alpha channel replaced.
+    pub fn with_alpha(&self, alpha: u8) -> Color {
+        // Only red and blue are switched, so alpha can be set directly
+        Color { alpha, .. *self }
+    }
+
+    /// Gets a copy of the color with the red channel replaced.
+    pub fn with_red(&self, red: u8) -> Color {
+        let (_, green, blue, alpha) = self.unswapped();
+        Color::rgba(red, green, blue, alpha)
+    }
+
+    /// Gets a copy of the color with the green channel replaced.
+    pub fn with_green(&self, green: u8) -> Color {
+        // Only red and blue are switched, so green can be set directly
+        Color { green, .. *self }
+    }
+
+    /// Gets a copy of the color with the blue channel replaced.
+    pub fn with_blue(&self, blue: u8) -> Color {
+        let (red, green, _, alpha) = self.unswapped();
+        Color::rgba(red, green, blue, alpha)
+    }
+
+    /// Adds `delta` to the red, green and blue channels, saturating at 0 and
+    /// 255. Unlike `lighten` and `darken` this is a plain offset of the
+    /// channels, so the hue can shift once a channel saturates.
+    /// The alpha channel is kept.
+    pub fn brightness_adjust(&self, delta: i16) -> Color {
+        let [red, green, blue, alpha] = self.channels();
+        let adjust = |channel: u8| (channel as i16).saturating_add(delta).clamp(0, 255) as u8;
+        Color::rgba(adjust(red), adjust(green), adjust(blue), alpha)
+    }
+
+    /// Gets a copy of the color with the alpha channel multiplied by `factor`,
+    /// which is clamped to 0.0-1.0.
+    pub fn scale_alpha(&self, factor: f32) -> Color {
+        let alpha = self.alpha as f32 * factor.clamp(0.0, 1.0);
+        self.with_alpha(Color::clamp_channel(alpha))
+    }
+
+    /// Applies the opacity of a window (from 0.0 to 1.0, clamped) to a color
+    /// drawn for it, eg its border, the same way inactive windows are dimmed.
+    ///
+    /// This is the same as `scale_alpha`.
+    pub fn apply_opacity_rule(&self, window_opacity: f32) -> Color {
+        self.scale_alpha(window_opacity)
+    }
+
+    /// Creates a new color from hue (in degrees), saturation and lightness
+    /// (both from 0.0 to 1.0) and an alphachannel.
+    ///
+    /// The hue wraps around at 360 degrees, saturation and lightness are clamped.
+    pub fn from_hsl(h: f32, s: f32, l: f32, a: u8) -> Color {
+        let h = ((h % 360.0) + 360.0) % 360.0;
+        let s = s.clamp(0.0, 1.0);
+        let l = l.clamp(0.0, 1.0);
+        let chroma = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        let (r, g, b) = Color::hue_to_rgb(h, chroma);
+        let m = l - chroma / 2.0;
+        Color::rgba(Color::channel_from_unit(r + m),
+                    Color::channel_from_unit(g + m),
+                    Color::channel_from_unit(b + m),
+                    a)
+    }
+
+    /// Converts the color into hue (in degrees, from 0.0 to 360.0),
+    /// saturation and lightness (both from 0.0 to 1.0).
+    ///
+    /// The alpha channel is ignored.
+    pub fn to_hsl(&self) -> (f32, f32, f32) {
+        let (h, max, min) = self.hue_max_min();
+        let l = (max + min) / 2.0;
+        let s = if max == min {
+            0.0
         } else {
-            None
+            (max - min) / (1.0 - (2.0 * l - 1.0).abs())
+        };
+        (h, s, l)
+    }
+
+    /// Creates a new color from hue (in degrees), saturation and value
+    /// (both from 0.0 to 1.0) and an alphachannel.
+    ///
+    /// The hue wraps around at 360 degrees, saturation and value are clamped.
+    pub fn from_hsv(h: f32, s: f32, v: f32, a: u8) -> Color {
+        let h = ((h % 360.0) + 360.0) % 360.0;
+        let s = s.clamp(0.0, 1.0);
+        let v = v.clamp(0.0, 1.0);
+        let chroma = v * s;
+        let (r, g, b) = Color::hue_to_rgb(h, chroma);
+        let m = v - chroma;
+        Color::rgba(Color::channel_from_unit(r + m),
+                    Color::channel_from_unit(g + m),
+                    Color::channel_from_unit(b + m),
+                    a)
+    }
+
+    /// Converts the color into hue (in degrees, from 0.0 to 360.0),
+    /// saturation and value (both from 0.0 to 1.0).
+    ///
+    /// The alpha channel is ignored.
+    pub fn to_hsv(&self) -> (f32, f32, f32) {
+        let (h, max, min) = self.hue_max_min();
+        let s = if max == 0.0 { 0.0 } else { (max - min) / max };
+        (h, s, max)
+    }
+
+    /// Creates a new color from hue (in degrees), whiteness and blackness
+    /// (both from 0.0 to 1.0) and an alphachannel.
+    ///
+    /// The hue wraps around at 360 degrees, whiteness and blackness are
+    /// clamped. If they add up to 1.0 or more, the color is a gray with
+    /// the lightness `whiteness / (whiteness + blackness)`.
+    pub fn from_hwb(h: f32, w: f32, b: f32, a: u8) -> Color {
+        let w = w.clamp(0.0, 1.0);
+        let b = b.clamp(0.0, 1.0);
+        if w + b >= 1.0 {
+            let gray = Color::channel_from_unit(w / (w + b));
+            return Color::rgba(gray, gray, gray, a)
         }
+        let h = ((h % 360.0) + 360.0) % 360.0;
+        let (red, green, blue) = Color::hue_to_rgb(h, 1.0);
+        let channel = |hue: f32| Color::channel_from_unit(hue * (1.0 - w - b) + w);
+        Color::rgba(channel(red), channel(green), channel(blue), a)
     }
 
-    /// Parses an ARGB String into a Color
-    fn parse_argb(s: &str) -> Option<Color> {
-        if s.len() == 8 {
-            let (str_a, str_rgb) = s.split_at(2);
-            // Due to the bug, the colors are already inverted, so in the returned color
-            // red is blue and blue is red.
-            let alpha  = Color::parse_color(str_a)?;
-            let colors = Color::parse_rgb(str_rgb);
-            colors.map(|rgb| Color::rgba(rgb.blue, rgb.green, rgb.red, alpha))
+    /// Converts the color into hue (in degrees, from 0.0 to 360.0),
+    /// whiteness and blackness (both from 0.0 to 1.0).
+    ///
+    /// The alpha channel is ignored. Grays have a hue of 0.0 and a
+    /// whiteness and blackness that add up to 1.0.
+    pub fn to_hwb(&self) -> (f32, f32, f32) {
+        let (h, max, min) = self.hue_max_min();
+        (h, min, 1.0 - max)
+    }
+
+    /// Creates the opaque color of a black body at the temperature in
+    /// Kelvin, eg to tint the screen like a night light.
+    ///
+    /// This uses Tanner Helland's approximation, so the temperature is
+    /// clamped to 1000K-40000K. 6600K is white, lower temperatures are
+    /// warmer (more red) and higher ones are cooler (more blue).
+    pub fn from_temperature(kelvin: f32) -> Color {
+        let temperature = kelvin.clamp(1000.0, 40000.0) / 100.0;
+        let (red, green, blue) = if temperature <= 66.0 {
+            let blue = if temperature <= 19.0 {
+                0.0
+            } else {
+                138.517_73 * (temperature - 10.0).ln() - 305.044_8
+            };
+            (255.0, 99.470_8 * temperature.ln() - 161.119_57, blue)
         } else {
-            None
+            (329.698_73 * (temperature - 60.0).powf(-0.133_204_76),
+             288.122_16 * (temperature - 60.0).powf(-0.075_514_85),
+             255.0)
+        };
+        Color::clamp_to_gamut(red, green, blue, 255.0)
+    }
+
+    /// Makes the color lighter by increasing its HSL lightness by `amount`
+    /// (from 0.0 to 1.0). The alpha channel is kept.
+    ///
+    /// `lighten(1.0)` always results in white.
+    pub fn lighten(&self, amount: f32) -> Color {
+        let (h, s, l) = self.to_hsl();
+        Color::from_hsl(h, s, l + amount.clamp(0.0, 1.0), self.alpha)
+    }
+
+    /// Makes the color darker by decreasing its HSL lightness by `amount`
+    /// (from 0.0 to 1.0). The alpha channel is kept.
+    ///
+    /// `darken(1.0)` always results in black.
+    pub fn darken(&self, amount: f32) -> Color {
+        let (h, s, l) = self.to_hsl();
+        Color::from_hsl(h, s, l - amount.clamp(0.0, 1.0), self.alpha)
+    }
+
+    /// Makes the color more colorful by increasing its HSL saturation by
+    /// `amount` (from 0.0 to 1.0). Hue, lightness and alpha are kept.
+    ///
+    /// Grays have no hue, so they are not changed.
+    pub fn saturate(&self, amount: f32) -> Color {
+        let (h, s, l) = self.to_hsl();
+        if s == 0.0 {
+            return *self
         }
+        Color::from_hsl(h, s + amount.clamp(0.0, 1.0), l, self.alpha)
     }
 
-    /// Parses a RGB String into a Color
-    fn parse_rgb(s: &str) -> Option<Color> {
-        if s.len() == 6 {
-            let (s_red, s_rest)   = s.split_at(2);
-            let (s_green, s_blue) = s_rest.split_at(2);
-            let red   = Color::parse_color(s_red)?;
-            let green = Color::parse_color(s_green)?;
-            let blue  = Color::parse_color(s_blue);
-            blue.map(|b| Color::rgba(red, green, b, 255))
+    /// Makes the color less colorful by decreasing its HSL saturation by
+    /// `amount` (from 0.0 to 1.0). Hue, lightness and alpha are kept.
+    ///
+    /// `desaturate(1.0)` always results in a gray with the same HSL
+    /// lightness, which unlike `grayscale` ignores the perceived brightness.
+    pub fn desaturate(&self, amount: f32) -> Color {
+        let (h, s, l) = self.to_hsl();
+        Color::from_hsl(h, s - amount.clamp(0.0, 1.0), l, self.alpha)
+    }
+
+    /// Shifts the HSL hue of the color by `degrees`, wrapping around at
+    /// 360 degrees. Saturation, lightness and alpha are kept.
+    ///
+    /// Rotating by 180 degrees gives the complementary color.
+    pub fn rotate_hue(&self, degrees: f32) -> Color {
+        let (h, s, l) = self.to_hsl();
+        Color::from_hsl(h + degrees, s, l, self.alpha)
+    }
+
+    /// Computes the hue (in degrees) and the biggest and smallest
+    /// channels (from 0.0 to 1.0) of the color.
+    fn hue_max_min(&self) -> (f32, f32, f32) {
+        let (r, g, b, _) = self.unswapped();
+        let (r, g, b) = (r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0);
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let delta = max - min;
+        let h = if delta == 0.0 {
+            0.0
+        } else if max == r {
+            60.0 * (((g - b) / delta) % 6.0)
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
         } else {
-            None
+            60.0 * ((r - g) / delta + 4.0)
+        };
+        let h = if h < 0.0 { h + 360.0 } else { h };
+        (h, max, min)
+    }
+
+    /// Computes the (red, green, blue) channels of a fully saturated hue
+    /// with the given chroma, before adding the lightness offset.
+    fn hue_to_rgb(h: f32, chroma: f32) -> (f32, f32, f32) {
+        let x = chroma * (1.0 - ((h / 60.0) % 2.0 - 1.0).abs());
+        match (h / 60.0) as u8 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x)
         }
     }
 
-    /// Parses exactly one single color value from a String (eg "AA", "RR", "GG" or "BB")
-    fn parse_color(s: &str) -> Option<u8> {
-        let mut chars = s.chars().take(2);
-        let digit1 = chars.next().and_then(Color::hex_to_u8)?;
-        let digit2 = chars.next().and_then(Color::hex_to_u8);
-        digit2.map(|i2| (digit1 << 4) | i2)
+    /// Composites this color over the `background` color, using the
+    /// Porter-Duff "over" operator.
+    ///
+    /// Both colors are expected to have straight (not premultiplied) alpha.
+    /// The blending is done on the sRGB values, see `blend_over_with`
+    /// to blend in linear light instead.
+    pub fn blend_over(&self, background: &Color) -> Color {
+        self.blend_over_with(background, GammaMode::Srgb)
     }
 
-    /// Converts a hex char into a u8
-    fn hex_to_u8(c: char) -> Option<u8> {
-        c.to_digit(16).map(|x| (x as u8))
+    /// Like `blend_over`, but blends the colors in the given `GammaMode`.
+    pub fn blend_over_with(&self, background: &Color, gamma: GammaMode) -> Color {
+        let (src_r, src_g, src_b, src_a) = self.unswapped();
+        let (dst_r, dst_g, dst_b, dst_a) = background.unswapped();
+        let src_a = src_a as f32 / 255.0;
+        let dst_a = dst_a as f32 / 255.0;
+        let out_a = src_a + dst_a * (1.0 - src_a);
+        if out_a == 0.0 {
+            return Color::TRANSPARENT
+        }
+        let blend = |src: u8, dst: u8| {
+            let src = gamma.decode(src);
+            let dst = gamma.decode(dst);
+            gamma.encode((src * src_a + dst * dst_a * (1.0 - src_a)) / out_a)
+        };
+        Color::rgba(blend(src_r, dst_r),
+                    blend(src_g, dst_g),
+                    blend(src_b, dst_b),
+                    Color::channel_from_unit(out_a))
     }
 
-}
+    /// Multiplies the channels of this color with those of `other`, which
+    /// always darkens. Blending with white has no effect, with black gives black.
+    ///
+    /// Like the other blend modes, this only blends the red, green and blue
+    /// channels, the alpha of this color is kept. Use `blend_over` to
+    /// composite the result onto `other`.
+    pub fn blend_multiply(&self, other: &Color) -> Color {
+        self.blend_channels(other, |top, bottom| top * bottom)
+    }
 
-impl From<u32> for Color {
-    fn from(val: u32) -> Self {
-        let red   = ((val & 0xff0000) >> 16) as u8;
-        let green = ((val & 0x00ff00) >> 8)  as u8;
-        let blue  =  (val & 0x0000ff)        as u8;
-        Color::rgba(red, green, blue, 255)
+    /// Multiplies the inverted channels, which always lightens. Blending
+    /// with black has no effect, with white gives white. The alpha of this
+    /// color is kept.
+    pub fn blend_screen(&self, other: &Color) -> Color {
+        self.blend_channels(other, |top, bottom| 1.0 - (1.0 - top) * (1.0 - bottom))
     }
-}
 
-#[cfg(test)]
-mod test {
+    /// Multiplies where `other` is dark and screens where it is light, which
+    /// increases the contrast of `other`. The alpha of this color is kept.
+    pub fn blend_overlay(&self, other: &Color) -> Color {
+        self.blend_channels(other, |top, bottom| {
+            if bottom < 0.5 {
+                2.0 * top * bottom
+            } else {
+                1.0 - 2.0 * (1.0 - top) * (1.0 - bottom)
+            }
+        })
+    }
 
-    use ::render::Color;
+    /// Blends the red, green and blue channels (from 0.0 to 1.0) of this
+    /// color on top of `other` with `blend`, keeping the alpha of this color.
+    fn blend_channels<F>(&self, other: &Color, blend: F) -> Color
+        where F: Fn(f32, f32) -> f32
+    {
+        let (top_r, top_g, top_b, alpha) = self.unswapped();
+        let (bottom_r, bottom_g, bottom_b, _) = other.unswapped();
+        let channel = |top: u8, bottom: u8| {
+            Color::channel_from_unit(blend(top as f32 / 255.0, bottom as f32 / 255.0))
+        };
+        Color::rgba(channel(top_r, bottom_r),
+                    channel(top_g, bottom_g),
+                    channel(top_b, bottom_b),
+                    alpha)
+    }
 
-    #[test]
-    fn test_from_u32() {
-        let hex_red   = 0xFF0000;
-        let hex_green = 0x00FF00;
-        let hex_blue  = 0x0000FF;
-        let r: Color = hex_red.into();
-        let g: Color = hex_green.into();
-        let b: Color = hex_blue.into();
-        // test red values
-        assert_eq!(0x00, r.red);
-        assert_eq!(0x00, r.green);
-        assert_eq!(0xFF, r.blue);
-        // test green values
-        assert_eq!(0x00, g.red);
-        assert_eq!(0xFF, g.green);
-        assert_eq!(0x00, g.blue);
-        // test blue values
-        assert_eq!(0xFF, b.red);
-        assert_eq!(0x00, b.green);
-        assert_eq!(0x00, b.blue);
+    /// Linearly interpolates between this color (at `t` = 0.0) and
+    /// the `other` color (at `t` = 1.0), including the alpha channel.
+    ///
+    /// `t` is clamped to 0.0-1.0. The interpolation is done on the sRGB
+    /// values, see `interpolate_with` to interpolate in linear light instead.
+    pub fn interpolate(&self, other: &Color, t: f32) -> Color {
+        self.interpolate_with(other, t, GammaMode::Srgb)
     }
 
-    #[test]
-    fn parse_color() {
-        // test all numbers, uppercase and lowercase letters
-        assert_eq!(17 * 0,  Color::parse_color("00").unwrap());
-        assert_eq!(17 * 1,  Color::parse_color("11").unwrap());
-        assert_eq!(17 * 2,  Color::parse_color("22").unwrap());
-        assert_eq!(17 * 3,  Color::parse_color("33").unwrap());
-        assert_eq!(17 * 4,  Color::parse_color("44").unwrap());
-        assert_eq!(17 * 5,  Color::parse_color("55").unwrap());
-        assert_eq!(17 * 6,  Color::parse_color("66").unwrap());
-        assert_eq!(17 * 7,  Color::parse_color("77").unwrap());
-        assert_eq!(17 * 8,  Color::parse_color("88").unwrap());
-        assert_eq!(17 * 9,  Color::parse_color("99").unwrap());
-        assert_eq!(17 * 10, Color::parse_color("aa").unwrap());
-        assert_eq!(17 * 10, Color::parse_color("AA").unwrap());
-        assert_eq!(17 * 11, Color::parse_color("bb").unwrap());
-        assert_eq!(17 * 11, Color::parse_color("BB").unwrap());
-        assert_eq!(17 * 12, Color::parse_color("cc").unwrap());
-        assert_eq!(17 * 12, Color::parse_color("CC").unwrap());
-        assert_eq!(17 * 13, Color::parse_color("dd").unwrap());
-        assert_eq!(17 * 13, Color::parse_color("DD").unwrap());
-        assert_eq!(17 * 14, Color::parse_color("ee").unwrap());
-        assert_eq!(17 * 14, Color::parse_color("EE").unwrap());
-        assert_eq!(17 * 15, Color::parse_color("ff").unwrap());
-        assert_eq!(17 * 15, Color::parse_color("FF").unwrap());
-        // test a few mixed values
-        assert_eq!(00,      Color::parse_color("00").unwrap());
-        assert_eq!(50,      Color::parse_color("32").unwrap());
-        assert_eq!(100,     Color::parse_color("64").unwrap());
-        assert_eq!(150,     Color::parse_color("96").unwrap());
-        assert_eq!(200,     Color::parse_color("c8").unwrap());
-        assert_eq!(250,     Color::parse_color("fa").unwrap());
-        assert_eq!(255,     Color::parse_color("ff").unwrap());
-        // test invalid values
-        assert_eq!(false,   Color::parse_color("").is_some());
-        assert_eq!(false,   Color::parse_color("h").is_some());
-        assert_eq!(false,   Color::parse_color("h2").is_some());
-        assert_eq!(false,   Color::parse_color("yz").is_some());
-        assert_eq!(false,   Color::parse_color("3x").is_some());
+    /// Like `interpolate`, but interpolates the colors in the given `GammaMode`.
+    ///
+    /// The alpha channel is always interpolated linearly.
+    pub fn interpolate_with(&self, other: &Color, t: f32, gamma: GammaMode) -> Color {
+        let t = t.clamp(0.0, 1.0);
+        let (from_r, from_g, from_b, from_a) = self.unswapped();
+        let (to_r, to_g, to_b, to_a) = other.unswapped();
+        let lerp = |from: f32, to: f32| from + (to - from) * t;
+        let lerp_color = |from: u8, to: u8| {
+            gamma.encode(lerp(gamma.decode(from), gamma.decode(to)))
+        };
+        let alpha = lerp(from_a as f32 / 255.0, to_a as f32 / 255.0);
+        Color::rgba(lerp_color(from_r, to_r),
+                    lerp_color(from_g, to_g),
+                    lerp_color(from_b, to_b),
+                    Color::channel_from_unit(alpha))
     }
 
-    #[test]
-    fn parse_rgb() {
-        // test some valid color values
-        let rgb_black = Color::parse_rgb("000000").unwrap();
-        assert_eq!(0,   rgb_black.red);
-        assert_eq!(0,   rgb_black.green);
-        assert_eq!(0,   rgb_black.blue);
-        assert_eq!(255, rgb_black.alpha);
-        let rgb_red   = Color::parse_rgb("ff0000").unwrap();
-        assert_eq!(0,   rgb_red.red);
-        assert_eq!(0,   rgb_red.green);
-        assert_eq!(255, rgb_red.blue);
-        assert_eq!(255, rgb_red.alpha);
-        let rgb_green = Color::parse_rgb("00ff00").unwrap();
-        assert_eq!(0,   rgb_green.red);
-        assert_eq!(255, rgb_green.green);
-        assert_eq!(0,   rgb_green.blue);
-        assert_eq!(255, rgb_green.alpha);
-        let rgb_blue  = Color::parse_rgb("0000ff").unwrap();
-        assert_eq!(255, rgb_blue.red);
-        assert_eq!(0,   rgb_blue.green);
-        assert_eq!(0,   rgb_blue.blue);
-        assert_eq!(255, rgb_blue.alpha);
-        let rgb_white = Color::parse_rgb("ffffff").unwrap();
-        assert_eq!(255, rgb_white.red);
-        assert_eq!(255, rgb_white.green);
-        assert_eq!(255, rgb_white.blue);
-        assert_eq!(255, rgb_white.alpha);
-        // test invalid formats
+    /// Computes `n` evenly spaced colors from this color to `other`, with
+    /// `interpolate`, eg to draw a gradient as a few solid blocks.
+    ///
+    /// With 2 or more steps the first is this color and the last is `other`.
+    /// A single step is just this color, no steps give an empty `Vec`.
+    pub fn gradient_steps(&self, other: &Color, n: usize) -> Vec<Color> {
+        match n {
+            0 => Vec::new(),
+            1 => vec![*self],
+            _ => (0..n).map(|i| self.interpolate(other, i as f32 / (n - 1) as f32)).collect()
+        }
+    }
+
+    /// Interpolates between this color (at `t` = 0.0) and the `other` color
+    /// (at `t` = 1.0) in HSL, which keeps the colors in between saturated
+    /// (eg red and green give yellow, instead of the brown `interpolate` gives).
+    ///
+    /// The hue takes the shorter way around the color wheel. Grays have no
+    /// hue, so the hue of the other color is used. Saturation, lightness
+    /// and alpha are interpolated linearly. `t` is clamped to 0.0-1.0.
+    pub fn lerp_hsl(&self, other: &Color, t: f32) -> Color {
+        let t = t.clamp(0.0, 1.0);
+        let (from_h, from_s, from_l) = self.to_hsl();
+        let (to_h, to_s, to_l) = other.to_hsl();
+        let lerp = |from: f32, to: f32| from + (to - from) * t;
+        let (from_h, to_h) = match (from_s == 0.0, to_s == 0.0) {
+            (true, false) => (to_h, to_h),
+            (false, true) => (from_h, from_h),
+            _ => (from_h, to_h)
+        };
+        // Go the other way around if that is shorter
+        let mut delta = to_h - from_h;
+        if delta > 180.0 {
+            delta -= 360.0;
+        } else if delta < -180.0 {
+            delta += 360.0;
+        }
+        let alpha = lerp(self.alpha as f32, other.alpha as f32);
+        Color::from_hsl(from_h + delta * t, lerp(from_s, to_s), lerp(from_l, to_l),
+                        Color::clamp_channel(alpha))
+    }
+
+    /// Computes the average of this color and `other`, including the alpha
+    /// channel. This is the midpoint `interpolate(other, 0.5)` computes, but
+    /// using only integer math.
+    ///
+    /// Halves are always rounded up, so the average of 0 and 255 is 128.
+    /// `interpolate` rounds halves to even, so the results can differ by one.
+    pub fn average_with(&self, other: &Color) -> Color {
+        let (red, green, blue, alpha) = self.unswapped();
+        let (other_red, other_green, other_blue, other_alpha) = other.unswapped();
+        let average = |a: u8, b: u8| (a as u16 + b as u16).div_ceil(2) as u8;
+        Color::rgba(average(red, other_red),
+                    average(green, other_green),
+                    average(blue, other_blue),
+                    average(alpha, other_alpha))
+    }
+
+    /// Computes the weighted average of the colors, including the alpha channel.
+    ///
+    /// Returns `None` if there are no colors, a weight is negative or
+    /// not a number, or the weights add up to zero.
+    pub fn mix(colors: &[(Color, f32)]) -> Option<Color> {
+        let mut total_weight = 0.0;
+        let mut sums = [0.0; 4];
+        for &(color, weight) in colors {
+            if weight < 0.0 || !weight.is_finite() {
+                return None
+            }
+            let (red, green, blue, alpha) = color.unswapped();
+            for (sum, channel) in sums.iter_mut().zip(&[red, green, blue, alpha]) {
+                *sum += *channel as f32 * weight;
+            }
+            total_weight += weight;
+        }
+        if total_weight == 0.0 {
+            return None
+        }
+        let average = |sum: f32| Color::clamp_channel(sum / total_weight);
+        Some(Color::rgba(average(sums[0]), average(sums[1]),
+                         average(sums[2]), average(sums[3])))
+    }
+
+    /// Multiplies the color channels with the alpha channel, which is the
+    /// format Cairo uses for its `ARgb32` buffers.
+    ///
+    /// The results are rounded to the nearest integer (there are no halves,
+    /// as 255 is odd), which keeps the error of `unpremultiply` small:
+    /// with an alpha of 128 or more, the channels come back off by at most 1.
+    /// With a lower alpha, fewer distinct values fit into the premultiplied
+    /// channels, so up to `255 / (2 * alpha)` is lost, eg all of a channel at alpha 1.
+    pub fn premultiply(&self) -> Color {
+        let (red, green, blue, alpha) = self.unswapped();
+        let multiply = |channel: u8| {
+            ((channel as u32 * alpha as u32 + 127) / 255) as u8
+        };
+        Color::rgba(multiply(red), multiply(green), multiply(blue), alpha)
+    }
+
+    /// Like `premultiply`, but fully transparent colors keep their color
+    /// channels instead of becoming transparent black.
+    ///
+    /// This is for interpolating premultiplied colors, eg a gradient from
+    /// transparent red to red, which shouldn't pass through dark red.
+    /// The transparent colors aren't valid premultiplied colors, so they
+    /// must not be drawn with Cairo.
+    pub fn premultiply_keep_hue(&self) -> Color {
+        if self.alpha == 0 {
+            *self
+        } else {
+            self.premultiply()
+        }
+    }
+
+    /// Divides the color channels by the alpha channel, which undoes `premultiply`.
+    ///
+    /// Precision is lost for colors with a low alpha, and colors that are
+    /// fully transparent become transparent black.
+    pub fn unpremultiply(&self) -> Color {
+        let (red, green, blue, alpha) = self.unswapped();
+        if alpha == 0 {
+            return Color::TRANSPARENT
+        }
+        let divide = |channel: u8| {
+            Color::clamp_channel(channel as f32 * 255.0 / alpha as f32)
+        };
+        Color::rgba(divide(red), divide(green), divide(blue), alpha)
+    }
+
+    /// Checks if the color is a valid premultiplied color, where no color
+    /// channel is bigger than the alpha channel.
+    ///
+    /// Cairo expects this of its `ARgb32` pixels, and some of its code
+    /// misbehaves for invalid pixels.
+    pub fn is_valid_premultiplied(&self) -> bool {
+        let (red, green, blue, alpha) = self.unswapped();
+        red <= alpha && green <= alpha && blue <= alpha
+    }
+
+    /// Makes the color a valid premultiplied color, by lowering the color
+    /// channels that are bigger than the alpha channel to it.
+    pub fn fix_premultiplied(&self) -> Color {
+        let (red, green, blue, alpha) = self.unswapped();
+        Color::rgba(red.min(alpha), green.min(alpha), blue.min(alpha), alpha)
+    }
+
+    /// Packs the color into a pixel of a Cairo `Format::ARgb32` surface.
+    ///
+    /// Cairo stores these pixels premultiplied, which is done here, with the
+    /// alpha in the upper 8 bits, then red, green and blue in the lowest 8 bits.
+    /// The `u32` is in native endianness, so written to the surface data
+    /// on a little-endian machine the bytes are in the order B, G, R, A.
+    pub fn as_cairo_argb32(&self) -> u32 {
+        let (red, green, blue, alpha) = self.premultiply().unswapped();
+        (alpha as u32) << 24 | (red as u32) << 16 | (green as u32) << 8 | blue as u32
+    }
+
+    /// Premultiplies and packs the color only once, for filling many
+    /// pixels with it (eg the background of a bar).
+    pub fn mul_alpha_into_u32(&self) -> PackedColor {
+        PackedColor(self.as_cairo_argb32())
+    }
+
+    /// Composites this color over a pixel of a `Format::ARgb32` surface,
+    /// given as its 4 bytes (premultiplied ARGB in native endianness),
+    /// and writes the result back into them.
+    ///
+    /// This uses the source-over operator directly on the premultiplied
+    /// channels, so the pixel doesn't lose precision by unpremultiplying it.
+    /// If `pixel` isn't exactly 4 bytes, it is left unchanged.
+    pub fn alpha_blend_onto(&self, pixel: &mut [u8]) -> Result<(), InvalidPixelLength> {
+        let bytes = <[u8; 4]>::try_from(&*pixel)
+            .map_err(|_| InvalidPixelLength { got: pixel.len() })?;
+        let src = self.as_cairo_argb32();
+        let dst = u32::from_ne_bytes(bytes);
+        let inverse_alpha = (255 - (src >> 24)) as f32 / 255.0;
+        let mut out = 0;
+        for shift in [24, 16, 8, 0] {
+            let dst_channel = Color::clamp_channel(((dst >> shift) & 0xFF) as f32 * inverse_alpha);
+            let channel = ((src >> shift) & 0xFF) + dst_channel as u32;
+            out |= channel.min(255) << shift;
+        }
+        pixel.copy_from_slice(&out.to_ne_bytes());
+        Ok(())
+    }
+
+    /// Converts the color into a gray of the same perceived brightness,
+    /// using the Rec. 709 luma weights. The alpha channel is kept.
+    pub fn grayscale(&self) -> Color {
+        let (red, green, blue, alpha) = self.unswapped();
+        let luma = 0.2126 * red as f32 + 0.7152 * green as f32 + 0.0722 * blue as f32;
+        let luma = Color::clamp_channel(luma);
+        Color::rgba(luma, luma, luma, alpha)
+    }
+
+    /// Inverts the red, green and blue channels. The alpha channel is kept.
+    pub fn invert(&self) -> Color {
+        let (red, green, blue, alpha) = self.unswapped();
+        Color::rgba(255 - red, 255 - green, 255 - blue, alpha)
+    }
+
+    /// Snaps the red, green and blue channels to the nearest of `levels`
+    /// evenly spaced values from 0 to 255, eg for a retro look or before
+    /// reducing the bit depth. The alpha channel is kept.
+    ///
+    /// Less than 2 levels are treated as 2, so every channel is 0 or 255.
+    pub fn quantize(&self, levels: u8) -> Color {
+        let step = 255.0 / (levels.max(2) - 1) as f32;
+        let snap = |channel: u8| Color::clamp_channel((channel as f32 / step).round() * step);
+        let (red, green, blue, alpha) = self.unswapped();
+        Color::rgba(snap(red), snap(green), snap(blue), alpha)
+    }
+
+    /// Computes the WCAG contrast ratio between the two colors, which ranges
+    /// from 1.0 (no contrast) to 21.0 (black and white).
+    ///
+    /// The alpha channels are ignored.
+    pub fn contrast_ratio(&self, other: &Color) -> f32 {
+        let l1 = self.luminance();
+        let l2 = other.luminance();
+        let (lighter, darker) = if l1 > l2 { (l1, l2) } else { (l2, l1) };
+        (lighter + 0.05) / (darker + 0.05)
+    }
+
+    /// Picks either opaque black or opaque white, whichever is more
+    /// readable as the color of text drawn on top of this color.
+    pub fn best_text_color(&self) -> Color {
+        if self.contrast_ratio(&Color::WHITE) > self.contrast_ratio(&Color::BLACK) {
+            Color::WHITE
+        } else {
+            Color::BLACK
+        }
+    }
+
+    /// Computes how different the colors look, as the CIE76 color
+    /// difference (the distance of the colors in the CIELAB color space).
+    ///
+    /// A distance of about 2.3 is just noticeable. The alpha channels are ignored.
+    pub fn distance(&self, other: &Color) -> f32 {
+        let (l1, a1, b1) = self.lab();
+        let (l2, a2, b2) = other.lab();
+        ((l1 - l2).powi(2) + (a1 - a2).powi(2) + (b1 - b2).powi(2)).sqrt()
+    }
+
+    /// Converts the color into CIELAB, with the D65 white point.
+    fn lab(&self) -> (f32, f32, f32) {
+        let (red, green, blue, _) = self.unswapped();
+        let (red, green, blue) = (Color::srgb_to_linear(red as f32 / 255.0),
+                                  Color::srgb_to_linear(green as f32 / 255.0),
+                                  Color::srgb_to_linear(blue as f32 / 255.0));
+        // CIE XYZ, relative to the white point
+        let x = (0.4124 * red + 0.3576 * green + 0.1805 * blue) / 0.95047;
+        let y = 0.2126 * red + 0.7152 * green + 0.0722 * blue;
+        let z = (0.0193 * red + 0.1192 * green + 0.9505 * blue) / 1.08883;
+        let f = |t: f32| {
+            if t > 216.0 / 24389.0 {
+                t.cbrt()
+            } else {
+                (24389.0 / 27.0 * t + 16.0) / 116.0
+            }
+        };
+        let (fx, fy, fz) = (f(x), f(y), f(z));
+        (116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz))
+    }
+
+    /// Gets the number (0-15) of the ANSI terminal color that is the nearest
+    /// to this color, according to `distance`. The alpha channel is ignored.
+    pub fn to_ansi16(&self) -> u8 {
+        ansi::nearest(self)
+    }
+
+    /// Creates an opaque color from the number of an ANSI terminal color,
+    /// using the default colors of xterm.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not in 0-15.
+    pub fn from_ansi16(index: u8) -> Color {
+        Color::from(ansi::ANSI_COLORS[index as usize])
+    }
+
+    /// Computes the WCAG relative luminance, from 0.0 (black) to 1.0 (white).
+    ///
+    /// The channels are converted into linear light and weighted by how
+    /// bright they appear, so green is much brighter than blue.
+    /// The alpha channel is ignored.
+    pub fn luminance(&self) -> f32 {
+        let (red, green, blue, _) = self.unswapped();
+        0.2126 * Color::srgb_to_linear(red as f32 / 255.0)
+            + 0.7152 * Color::srgb_to_linear(green as f32 / 255.0)
+            + 0.0722 * Color::srgb_to_linear(blue as f32 / 255.0)
+    }
+
+    /// Converts a sRGB encoded value into linear light, both from 0.0 to 1.0.
+    pub(super) fn srgb_to_linear(value: f32) -> f32 {
+        if value <= 0.04045 {
+            value / 12.92
+        } else {
+            ((value + 0.055) / 1.055).powf(2.4)
+        }
+    }
+
+    /// Converts a channel from 0.0-1.0 to 0-255, clamping out of range values.
+    fn channel_from_unit(value: f32) -> u8 {
+        Color::clamp_channel(value * 255.0)
+    }
+
+    /// Converts the result of a calculation on a channel back into a channel.
+    ///
+    /// The value is rounded to the nearest integer, with halves rounded to
+    /// the even one (so 127.5 becomes 128, but 128.5 does as well), and then
+    /// clamped to 0-255. Not a number becomes 0.
+    ///
+    /// All the calculations on channels go through this, so they round the same.
+    pub(super) fn clamp_channel(value: f32) -> u8 {
+        if value.is_nan() {
+            return 0
+        }
+        value.round_ties_even().clamp(0.0, 255.0) as u8
+    }
+
+    /// Converts linear light into a sRGB encoded value, both from 0.0 to 1.0.
+    pub(super) fn linear_to_srgb(value: f32) -> f32 {
+        if value <= 0.0031308 {
+            value * 12.92
+        } else {
+            1.055 * value.powf(1.0 / 2.4) - 0.055
+        }
+    }
+
+    /// Serializes the color into one of the formats understood by `parse`.
+    ///
+    /// The digits are uppercase, and the alpha channel is only emitted
+    /// (as "AARRGGBB") if `include_alpha` is set.
+    pub fn to_hex_string(&self, include_alpha: bool, prefix: HexPrefix) -> String {
+        let prefix = match prefix {
+            HexPrefix::None => "",
+            HexPrefix::Hash => "#",
+            HexPrefix::ZeroX => "0x"
+        };
+        let (red, green, blue, alpha) = self.unswapped();
+        if include_alpha {
+            format!("{}{:02X}{:02X}{:02X}{:02X}", prefix, alpha, red, green, blue)
+        } else {
+            format!("{}{:02X}{:02X}{:02X}", prefix, red, green, blue)
+        }
+    }
+
+    /// Serializes the color into the shortest hex format `parse` reads back
+    /// as the same color, with a "#" prefix and lowercase digits.
+    ///
+    /// Opaque colors are "#rgb" if both digits of every channel are the same
+    /// (eg "#f00" for "#ff0000"), otherwise "#rrggbb". Other colors are
+    /// "#rgba" in the same case, otherwise "#aarrggbb" (note where the alpha is).
+    pub fn simplest_hex(&self) -> String {
+        let (red, green, blue, alpha) = self.unswapped();
+        let is_short = |channel: u8| channel >> 4 == channel & 0xF;
+        let short = [red, green, blue, alpha].iter().all(|&channel| is_short(channel));
+        match (self.is_opaque(), short) {
+            (true, true) => format!("#{:x}{:x}{:x}", red & 0xF, green & 0xF, blue & 0xF),
+            (true, false) => format!("#{:02x}{:02x}{:02x}", red, green, blue),
+            (false, true) => format!("#{:x}{:x}{:x}{:x}",
+                                     red & 0xF, green & 0xF, blue & 0xF, alpha & 0xF),
+            (false, false) => format!("#{:02x}{:02x}{:02x}{:02x}", alpha, red, green, blue)
+        }
+    }
+
+    /// Serializes the color into the CSS notation every browser understands,
+    /// "rgb(R, G, B)" for opaque colors and "rgba(R, G, B, A)" otherwise.
+    ///
+    /// The alpha is a number from 0 to 1 with at most 3 decimals, which is
+    /// enough for `parse` to read back the same color.
+    pub fn to_css_string(&self) -> String {
+        let (red, green, blue, alpha) = self.unswapped();
+        if self.is_opaque() {
+            return format!("rgb({}, {}, {})", red, green, blue)
+        }
+        let alpha = format!("{:.3}", alpha as f32 / 255.0);
+        let alpha = alpha.trim_end_matches('0').trim_end_matches('.');
+        format!("rgba({}, {}, {}, {})", red, green, blue, alpha)
+    }
+
+    /// Serializes the color into a JSON object with the real channels,
+    /// eg `{"r":255,"g":0,"b":0,"a":255}`.
+    ///
+    /// This is the object format the `serde` feature accepts, but
+    /// doesn't need that feature.
+    pub fn to_json_object(&self) -> String {
+        let (red, green, blue, alpha) = self.unswapped();
+        format!(r#"{{"r":{},"g":{},"b":{},"a":{}}}"#, red, green, blue, alpha)
+    }
+
+    /// Gets the CSS name of the color, if it has one, eg "red" for "#ff0000".
+    ///
+    /// Only opaque colors have names, except for transparent black, which
+    /// is "transparent". If the color has multiple names (eg "aqua" and
+    /// "cyan"), the first one alphabetically is used.
+    pub fn name(&self) -> Option<&'static str> {
+        if *self == Color::TRANSPARENT {
+            Some("transparent")
+        } else if self.is_opaque() {
+            color_names::name_of(self.to_argb_u32() & 0xFFFFFF)
+        } else {
+            None
+        }
+    }
+
+    /// Gets the CSS name of the color that looks the most like this one,
+    /// according to `distance`, eg for logging computed colors.
+    ///
+    /// The alpha channel is ignored. If multiple names are the closest,
+    /// the first one alphabetically is used, like in `name`.
+    pub fn closest_named(&self) -> &'static str {
+        let opaque = self.with_alpha(255);
+        color_names::NAMED_COLORS.iter()
+            .map(|&(name, rgb)| (name, opaque.distance(&Color::from(rgb))))
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(name, _)| name)
+            .expect("There are named colors")
+    }
+
+    /// Parses a String into a Color
+    /// The following formats are supported:
+    /// - "RRGGBB"
+    /// - "AARRGGBB"
+    /// - "#RRGGBB"
+    /// - "#AARRGGBB"
+    /// - "0xRRGGBB"
+    /// - "0xAARRGGBB"
+    /// - "0XRRGGBB" and "0XAARRGGBB"
+    /// - "RGB", "#RGB" and "0xRGB"
+    /// - "RGBA", "#RGBA" and "0xRGBA"
+    /// - "RRRRGGGGBBBB" and "AAAARRRRGGGGBBBB", with any of the prefixes
+    /// - "rgb(R, G, B)"
+    /// - "rgba(R, G, B, A)"
+    /// - "rgb(R G B)" and "rgb(R G B / A)", where A is a number from 0.0
+    ///   to 1.0 or a percentage
+    /// - "hsl(H, S, L)"
+    /// - "hsla(H, S, L, A)"
+    /// - "hsl(H S L)" and "hsl(H S L / A)", with S and L as percentages
+    /// - "hsv(H, S, V)"
+    /// - "hsva(H, S, V, A)"
+    /// - "hwb(H W B)" and "hwb(H W B / A)", with W and B as percentages
+    /// - "rgb:R/G/B", like in X11, where every channel has 1 to 4 hex digits
+    /// - "R,G,B" and "R,G,B,A", where every channel is an integer from 0 to 255
+    /// - CSS color names, eg "red" or "CornflowerBlue" (case-insensitive)
+    ///
+    /// Whitespace around the color is ignored, but not within it
+    /// (except for the functional notations). So is a single semicolon
+    /// after the color, as in a CSS declaration (eg "#ff0000;").
+    ///
+    /// The shorthand formats duplicate each digit, so "#f00" is the same as "#ff0000".
+    /// NOTE: Like in CSS, the alpha of the 4 digit shorthand comes last,
+    /// unlike in the 8 digit format. So "#f008" is the same as "#88ff0000".
+    /// The 12 and 16 digit formats have 16 bits per channel, of which only
+    /// the high 8 bits are kept. So "#ff80ff80ff80" is the same as "#ffffff".
+    ///
+    /// In the functional notations the RGB channels are integers from 0 to 255
+    /// or percentages (eg "50%"). The hue is in degrees (optionally with the
+    /// "deg" unit, or in turns with "turn") and wraps around at 360, saturation, lightness
+    /// and value are either a float from 0.0 to 1.0 or a percentage (eg "50%").
+    /// The alpha channel is either a float from 0.0 to 1.0 (if it contains
+    /// a decimal point, eg "0.5" or "1.0") or an integer from 0 to 255.
+    ///
+    /// This is the same as `parse_with` in the `ParseMode::Lenient` mode.
+    pub fn parse(s: &str) -> Option<Color> {
+        let s = Color::strip_semicolon(s.trim_matches(|c: char| c.is_ascii_whitespace()));
+        if s.starts_with('#') || Color::has_hex_prefix(s) {
+            // Fast path, neither names nor functions start like this
+            Color::parse_hex(s)
+        } else if let Some(triplet) = s.strip_prefix("rgb:") {
+            Color::parse_x11(triplet)
+        } else if s.ends_with(")") {
+            Color::parse_function(s)
+        } else if s.contains(',') {
+            Color::parse_channel_list(s)
+        } else if let Some(color) = Color::parse_named(s) {
+            Some(color)
+        } else {
+            Color::parse_hex(s)
+        }
+    }
+
+    /// Parses a String into a Color like `parse`, but remembers the results
+    /// (in a cache per thread), so parsing the same string again is cheap.
+    ///
+    /// This is worth it for the functional notations and color names, which
+    /// are much slower to parse than to look up. Hex colors with a prefix
+    /// are parsed faster than they could be looked up, so they aren't cached.
+    pub fn parse_cached(s: &str) -> Option<Color> {
+        let trimmed = s.trim_start_matches(|c: char| c.is_ascii_whitespace());
+        if trimmed.starts_with('#') || Color::has_hex_prefix(trimmed) {
+            return Color::parse(s)
+        }
+        PARSE_CACHE.with(|cache| {
+            if let Some(&color) = cache.borrow().get(s) {
+                return color
+            }
+            let color = Color::parse(s);
+            let mut cache = cache.borrow_mut();
+            if cache.len() >= PARSE_CACHE_CAPACITY {
+                cache.clear();
+            }
+            cache.insert(s.to_owned(), color);
+            color
+        })
+    }
+
+    /// Parses a String into a Color, accepting the formats of the `mode`.
+    pub fn parse_with(s: &str, mode: ParseMode) -> Option<Color> {
+        match mode {
+            ParseMode::Lenient => Color::parse(s),
+            ParseMode::Strict => {
+                let digits = s.strip_prefix('#')?;
+                match digits.len() {
+                    8 => Color::parse_argb(digits),
+                    6 => Color::parse_rgb(digits),
+                    _ => None
+                }
+            }
+        }
+    }
+
+    /// Parses a String into a Color like `parse`, but with the alpha channel
+    /// of the 8 and 16 digit hex formats where `order` says.
+    ///
+    /// The other formats are not affected, eg the alpha of the 4 digit
+    /// shorthand always comes last.
+    pub fn parse_with_order(s: &str, order: HexOrder) -> Option<Color> {
+        let color = Color::parse(s)?;
+        if order == HexOrder::Argb {
+            return Some(color)
+        }
+        // Strip the color the same way `parse` does
+        let trimmed = Color::strip_semicolon(s.trim_matches(|c: char| c.is_ascii_whitespace()));
+        let (digits, _) = Color::strip_hex_prefixes(trimmed);
+        let has_alpha = digits.len() == 8 || digits.len() == 16;
+        if has_alpha && digits.bytes().all(|digit| digit.is_ascii_hexdigit()) {
+            // `parse` read the channels shifted by one
+            let (green, blue, alpha, red) = color.unswapped();
+            Some(Color::rgba(red, green, blue, alpha))
+        } else {
+            Some(color)
+        }
+    }
+
+    /// Parses a color in the syntax of the CSS Color Module Level 4.
+    ///
+    /// This is stricter than `parse` in some ways (following CSS) and
+    /// understands some notations `parse` doesn't. The supported subset is:
+    /// - "#RGB", "#RGBA", "#RRGGBB" and "#RRGGBBAA". NOTE: Like in CSS, the
+    ///   alpha comes last, unlike in the 8 digit format of `parse`.
+    /// - CSS color names and "transparent" (case-insensitive)
+    /// - "rgb(R, G, B)" and "rgb(R, G, B, A)", where R, G and B are integers
+    ///   from 0 to 255 or percentages, and A is a number from 0.0 to 1.0
+    ///   or a percentage.
+    /// - "rgb(R G B)" and "rgb(R G B / A)", the same without commas.
+    /// - "hsl(H, S, L)", "hsl(H, S, L, A)", "hsl(H S L)" and "hsl(H S L / A)",
+    ///   where H is a number of degrees, optionally with the "deg" or "turn"
+    ///   unit, and S and L are percentages.
+    /// - "hwb(H W B)" and "hwb(H W B / A)", where W and B are percentages.
+    ///   Like in CSS, hwb has no form with commas.
+    /// - "rgba" and "hsla" are the same as "rgb" and "hsl".
+    ///
+    /// Function names are case-insensitive, whitespace around the color is ignored.
+    /// Not supported are eg `none` channels, `calc()`, relative colors
+    /// and the other color spaces (lab, oklch, color(...)).
+    pub fn parse_css_color4(s: &str) -> Result<Color, ColorParseError> {
+        let trimmed = s.trim_start_matches(|c: char| c.is_ascii_whitespace());
+        let offset = s.len() - trimmed.len();
+        let s = trimmed.trim_end_matches(|c: char| c.is_ascii_whitespace());
+        if s.is_empty() {
+            Err(ColorParseError::EmptyInput)
+        } else if let Some(digits) = s.strip_prefix('#') {
+            Color::parse_css_hex(digits, offset + 1)
+        } else if let Some(open) = s.find('(') {
+            let args = s[open + 1..].strip_suffix(')')
+                .ok_or(ColorParseError::InvalidFunction)?;
+            Color::parse_css_function(&s[..open], args)
+                .ok_or(ColorParseError::InvalidFunction)
+        } else {
+            Color::parse_named(s).ok_or(ColorParseError::UnknownName)
+        }
+    }
+
+    /// Parses the digits of a CSS hex color, which start at the (byte) `offset`.
+    fn parse_css_hex(digits: &str, offset: usize) -> Result<Color, ColorParseError> {
+        if let Some((position, found)) = digits.chars().enumerate()
+            .find(|&(_, c)| !c.is_ascii_hexdigit()) {
+            return Err(ColorParseError::InvalidDigit { position: offset + position, found })
+        }
+        let color = match digits.len() {
+            3 | 4 => Color::parse_short(digits),
+            6 => Color::parse_rgb(digits),
+            8 => Color::parse_rgb(&digits[..6]).and_then(|rgb| {
+                Color::parse_color(&digits[6..]).map(|alpha| rgb.with_alpha(alpha))
+            }),
+            _ => None
+        };
+        color.ok_or(ColorParseError::InvalidLength { got: digits.len() })
+    }
+
+    /// Parses a CSS color function, given its name and what is inside of the parentheses.
+    fn parse_css_function(name: &str, args: &str) -> Option<Color> {
+        let name = name.to_ascii_lowercase();
+        let (channels, alpha) = Color::split_css_args(args)?;
+        if channels.len() != 3 || (name == "hwb" && args.contains(',')) {
+            return None
+        }
+        let alpha = match alpha {
+            Some(alpha) => Color::channel_from_unit(Color::parse_unit(alpha)?),
+            None => 255
+        };
+        match name.as_str() {
+            "rgb" | "rgba" => Some(Color::rgba(Color::parse_channel(channels[0])?,
+                                               Color::parse_channel(channels[1])?,
+                                               Color::parse_channel(channels[2])?,
+                                               alpha)),
+            "hsl" | "hsla" => Some(Color::from_hsl(Color::parse_hue(channels[0])?,
+                                                   Color::parse_percentage(channels[1])?,
+                                                   Color::parse_percentage(channels[2])?,
+                                                   alpha)),
+            "hwb" => Some(Color::from_hwb(Color::parse_hue(channels[0])?,
+                                          Color::parse_percentage(channels[1])?,
+                                          Color::parse_percentage(channels[2])?,
+                                          alpha)),
+            _ => None
+        }
+    }
+
+    /// Splits the arguments of a CSS color function into the channels and
+    /// the alpha channel, if there is one.
+    ///
+    /// The arguments are either separated by commas ("R, G, B, A") or by
+    /// whitespace, with the alpha after a slash ("R G B / A").
+    fn split_css_args(args: &str) -> Option<(Vec<&str>, Option<&str>)> {
+        if args.contains(',') {
+            if args.contains('/') {
+                return None
+            }
+            let mut channels: Vec<&str> = args.split(',').map(|arg| arg.trim()).collect();
+            let alpha = if channels.len() == 4 { channels.pop() } else { None };
+            Some((channels, alpha))
+        } else {
+            let mut parts = args.splitn(2, '/');
+            let channels = parts.next()?.split_ascii_whitespace().collect();
+            let alpha = match parts.next().map(|alpha| alpha.trim()) {
+                Some(alpha) if alpha.is_empty() || alpha.contains(char::is_whitespace) =>
+                    return None,
+                alpha => alpha
+            };
+            Some((channels, alpha))
+        }
+    }
+
+    /// Parses one of the hex formats into a Color
+    fn parse_hex(s: &str) -> Option<Color> {
+        if !s.is_ascii() {
+            // Hex digits are ASCII, and the length checks below count bytes
+            return None
+        }
+        let (digits, _) = Color::strip_hex_prefixes(s);
+        match digits.len() {
+            3 | 4 => Color::parse_short(digits),
+            6 => Color::parse_rgb(digits),
+            8 => Color::parse_argb(digits),
+            12 | 16 => Color::parse_deep(digits),
+            _ => None
+        }
+    }
+
+    /// Strips all the "#", "0x" and "0X" prefixes from the start of the string.
+    ///
+    /// Returns the rest of the string and the number of bytes stripped.
+    fn strip_hex_prefixes(s: &str) -> (&str, usize) {
+        let mut rest = s;
+        loop {
+            if rest.starts_with('#') {
+                rest = &rest[1..];
+            } else if Color::has_hex_prefix(rest) {
+                rest = &rest[2..];
+            } else {
+                return (rest, s.len() - rest.len())
+            }
+        }
+    }
+
+    /// Strips a single semicolon at the end, and the whitespace before it.
+    fn strip_semicolon(s: &str) -> &str {
+        s.strip_suffix(';')
+            .map_or(s, |s| s.trim_end_matches(|c: char| c.is_ascii_whitespace()))
+    }
+
+    /// Checks for the "0x" prefix, which may also be written as "0X".
+    fn has_hex_prefix(s: &str) -> bool {
+        s.starts_with("0x") || s.starts_with("0X")
+    }
+
+    /// Parses a String into a Color, like `parse`.
+    ///
+    /// On failure the reason the string was rejected is returned,
+    /// which is more useful to report to the user than `None`.
+    pub fn try_parse(s: &str) -> Result<Color, ColorParseError> {
+        Color::parse(s).ok_or_else(|| Color::diagnose(s))
+    }
+
+    /// Parses a String into a Color like `try_parse`, but also accepts
+    /// "none" (case-insensitive), for which nothing should be drawn at all.
+    ///
+    /// Unlike "none", "transparent" is a color: transparent black.
+    pub fn parse_optional(s: &str) -> Result<Option<Color>, ColorParseError> {
+        let trimmed = Color::strip_semicolon(s.trim_matches(|c: char| c.is_ascii_whitespace()));
+        if trimmed.eq_ignore_ascii_case("none") {
+            Ok(None)
+        } else {
+            Color::try_parse(s).map(Some)
+        }
+    }
+
+    /// Parses a list of colors, separated by commas and/or whitespace,
+    /// eg "#fff, #000 0xFF00FF00".
+    ///
+    /// Separators inside of functional notations (eg "rgb(0, 0, 0)") don't
+    /// split the color. If a color is invalid, the error contains its index.
+    pub fn parse_many(s: &str) -> Result<Vec<Color>, ColorParseError> {
+        let mut colors = Vec::new();
+        let mut start = None;
+        let mut depth = 0;
+        let is_separator = |c: char| c == ',' || c.is_whitespace();
+        for (index, c) in s.char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' if depth > 0 => depth -= 1,
+                c if depth == 0 && is_separator(c) => {
+                    if let Some(token_start) = start.take() {
+                        colors.push(Color::parse_entry(&s[token_start..index], colors.len())?);
+                    }
+                    continue
+                },
+                _ => {}
+            }
+            if start.is_none() {
+                start = Some(index);
+            }
+        }
+        if let Some(token_start) = start {
+            colors.push(Color::parse_entry(&s[token_start..], colors.len())?);
+        }
+        Ok(colors)
+    }
+
+    /// Parses one color of a list for `parse_many`.
+    fn parse_entry(s: &str, index: usize) -> Result<Color, ColorParseError> {
+        Color::try_parse(s).map_err(|reason| ColorParseError::InvalidEntry {
+            index,
+            reason: Box::new(reason)
+        })
+    }
+
+    /// Figures out why `parse` rejected the string.
+    ///
+    /// Should only be called with strings that `parse` failed on.
+    fn diagnose(s: &str) -> ColorParseError {
+        // Strip the surrounding whitespace the same way `parse` does
+        let trimmed = s.trim_start_matches(|c: char| c.is_ascii_whitespace());
+        let mut offset = s.len() - trimmed.len();
+        let s = trimmed.trim_end_matches(|c: char| c.is_ascii_whitespace());
+        let s = Color::strip_semicolon(s);
+        if s.is_empty() {
+            return ColorParseError::EmptyInput
+        }
+        if s.contains("(") || s.contains(")") || s.starts_with("rgb:") || s.contains(',') {
+            return ColorParseError::InvalidFunction
+        }
+        // Strip the prefixes the same way `parse` does
+        let (rest, stripped) = Color::strip_hex_prefixes(s);
+        // Color names are only letters, so without a prefix a word that
+        // can't be hex was meant to be a name (eg a typo like "reddish")
+        if stripped == 0 && rest.chars().all(|c| c.is_ascii_alphabetic())
+            && !rest.chars().all(|c| c.is_ascii_hexdigit()) {
+            return ColorParseError::UnknownName
+        }
+        offset += stripped;
+        let len = rest.chars().count();
+        if ![3, 4, 6, 8, 12, 16].contains(&len) {
+            return ColorParseError::InvalidLength { got: len }
+        }
+        match rest.chars().enumerate().find(|&(_, c)| !c.is_ascii_hexdigit()) {
+            Some((position, found)) => ColorParseError::InvalidDigit {
+                position: offset + position,
+                found
+            },
+            None => ColorParseError::InvalidLength { got: len }
+        }
+    }
+
+    /// Parses a CSS color name into a Color
+    fn parse_named(s: &str) -> Option<Color> {
+        if s.eq_ignore_ascii_case("transparent") {
+            return Some(Color::TRANSPARENT)
+        }
+        color_names::lookup(s).map(Color::from)
+    }
+
+    /// Parses a functional notation, eg "rgb(255, 0, 0)", into a Color
+    fn parse_function(s: &str) -> Option<Color> {
+        let open = s.find('(')?;
+        if !s.ends_with(")") {
+            return None
+        }
+        let name = &s[..open];
+        let args = &s[open + 1..s.len() - 1];
+        match name {
+            // The syntax of CSS Color 4 without commas, eg "rgb(255 0 0 / 50%)".
+            // Like in CSS, hwb() only has this syntax.
+            "rgb" | "rgba" | "hsl" | "hsla" if !args.contains(',') =>
+                return Color::parse_css_function(name, args),
+            "hwb" => return Color::parse_css_function(name, args),
+            _ => {}
+        }
+        let args: Vec<&str> = args.split(',').map(|arg| arg.trim()).collect();
+        match name {
+            "rgb" | "rgba" => Color::parse_rgb_function(&args),
+            "hsl" | "hsla" => Color::parse_hue_function(&args, Color::from_hsl),
+            "hsv" | "hsva" => Color::parse_hue_function(&args, Color::from_hsv),
+            _ => None
+        }
+    }
+
+    /// Parses a list of 3 or 4 channels separated by commas, eg "255,0,0"
+    /// or "255, 0, 0, 128", which is "rgb(...)" without the function.
+    ///
+    /// Every channel is an integer from 0 to 255, including the alpha.
+    fn parse_channel_list(s: &str) -> Option<Color> {
+        let channels = s.split(',')
+            .map(|channel| channel.trim().parse::<u8>().ok())
+            .collect::<Option<Vec<u8>>>()?;
+        match channels[..] {
+            [red, green, blue] => Some(Color::rgba(red, green, blue, 255)),
+            [red, green, blue, alpha] => Some(Color::rgba(red, green, blue, alpha)),
+            _ => None
+        }
+    }
+
+    /// Parses the "R/G/B" of the X11 "rgb:R/G/B" notation.
+    ///
+    /// Every channel has 1 to 4 hex digits, which are scaled to 0-255,
+    /// so "f" becomes 255 and "8000" becomes 128.
+    fn parse_x11(s: &str) -> Option<Color> {
+        let mut channels = [0; 3];
+        let mut parts = s.split('/');
+        for channel in channels.iter_mut() {
+            let digits = parts.next()?;
+            if digits.is_empty() || digits.len() > 4
+                || !digits.bytes().all(|digit| digit.is_ascii_hexdigit()) {
+                return None
+            }
+            let value = u16::from_str_radix(digits, 16).ok()?;
+            let max = u16::MAX >> (16 - 4 * digits.len());
+            *channel = Color::clamp_channel(value as f32 * 255.0 / max as f32);
+        }
+        if parts.next().is_some() {
+            return None
+        }
+        let [red, green, blue] = channels;
+        Some(Color::rgba(red, green, blue, 255))
+    }
+
+    /// Parses the arguments of the "rgb(...)" and "rgba(...)" notation
+    fn parse_rgb_function(args: &[&str]) -> Option<Color> {
+        if args.len() != 3 && args.len() != 4 {
+            return None
+        }
+        let red   = Color::parse_channel(args[0])?;
+        let green = Color::parse_channel(args[1])?;
+        let blue  = Color::parse_channel(args[2])?;
+        let alpha = match args.get(3) {
+            Some(alpha) => Color::parse_alpha(alpha)?,
+            None => 255
+        };
+        Some(Color::rgba(red, green, blue, alpha))
+    }
+
+    /// Parses a channel of the "rgb(...)" notation, either as an integer in
+    /// 0-255 or as a percentage (eg "50%").
+    ///
+    /// Like in CSS, integers and percentages may be mixed in one color,
+    /// although that is discouraged.
+    fn parse_channel(s: &str) -> Option<u8> {
+        match s.strip_suffix('%') {
+            Some(percent) => {
+                let percent = percent.parse::<f32>().ok()?;
+                if (0.0..=100.0).contains(&percent) {
+                    Some(Color::channel_from_unit(percent / 100.0))
+                } else {
+                    None
+                }
+            },
+            None => s.parse::<u8>().ok()
+        }
+    }
+
+    /// Parses the arguments of the "hsl(...)", "hsla(...)", "hsv(...)" and
+    /// "hsva(...)" notations, creating the color with `from_hue`.
+    fn parse_hue_function(args: &[&str], from_hue: fn(f32, f32, f32, u8) -> Color)
+                          -> Option<Color> {
+        if args.len() != 3 && args.len() != 4 {
+            return None
+        }
+        let hue = Color::parse_hue(args[0])?;
+        let saturation = Color::parse_unit(args[1])?;
+        let lightness_or_value = Color::parse_unit(args[2])?;
+        let alpha = match args.get(3) {
+            Some(alpha) => Color::parse_alpha(alpha)?,
+            None => 255
+        };
+        Some(from_hue(hue, saturation, lightness_or_value, alpha))
+    }
+
+    /// Parses a hue in degrees, either as a bare number, with the "deg"
+    /// unit or with the "turn" unit (eg "0.5turn" is 180 degrees).
+    fn parse_hue(s: &str) -> Option<f32> {
+        let hue = if let Some(turns) = s.strip_suffix("turn") {
+            turns.parse::<f32>().ok()? * 360.0
+        } else if let Some(degrees) = s.strip_suffix("deg") {
+            degrees.parse::<f32>().ok()?
+        } else {
+            s.parse::<f32>().ok()?
+        };
+        if hue.is_finite() {
+            Some(hue)
+        } else {
+            None
+        }
+    }
+
+    /// Parses a value from 0.0 to 1.0, either as a float or as a
+    /// percentage (eg "50%").
+    fn parse_unit(s: &str) -> Option<f32> {
+        let value = if let Some(percent) = s.strip_suffix('%') {
+            percent.parse::<f32>().ok()? / 100.0
+        } else {
+            s.parse::<f32>().ok()?
+        };
+        if (0.0..=1.0).contains(&value) {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    /// Parses a percentage (eg "50%") into a value from 0.0 to 1.0.
+    fn parse_percentage(s: &str) -> Option<f32> {
+        if s.ends_with('%') {
+            Color::parse_unit(s)
+        } else {
+            None
+        }
+    }
+
+    /// Parses an alpha value, either as a float in 0.0-1.0 (if it has
+    /// a decimal point) or as an integer in 0-255.
+    fn parse_alpha(s: &str) -> Option<u8> {
+        if s.contains('.') {
+            let alpha = s.parse::<f32>().ok()?;
+            if (0.0..=1.0).contains(&alpha) {
+                Some(Color::channel_from_unit(alpha))
+            } else {
+                None
+            }
+        } else {
+            s.parse::<u8>().ok()
+        }
+    }
+
+    /// Parses an ARGB String into a Color
+    fn parse_argb(s: &str) -> Option<Color> {
+        let bytes = s.as_bytes();
+        if bytes.len() == 8 {
+            let alpha  = Color::parse_hex_byte(&bytes[..2])?;
+            let colors = Color::parse_rgb_bytes(&bytes[2..]);
+            colors.map(|rgb| Color { alpha, .. rgb })
+        } else {
+            None
+        }
+    }
+
+    /// Parses a String with 16 bits per channel ("RRRRGGGGBBBB" or
+    /// "AAAARRRRGGGGBBBB") into a Color, keeping the high byte of every channel.
+    fn parse_deep(s: &str) -> Option<Color> {
+        let bytes = s.as_bytes();
+        let first_channel = match bytes.len() {
+            16 => 0,
+            12 => 1,
+            _ => return None
+        };
+        let mut channels = [255; 4];
+        for (index, digits) in bytes.chunks(4).enumerate() {
+            // The low byte is dropped, but it still has to be valid
+            Color::parse_hex_byte(&digits[2..])?;
+            channels[first_channel + index] = Color::parse_hex_byte(digits)?;
+        }
+        let [alpha, red, green, blue] = channels;
+        Some(Color::rgba(red, green, blue, alpha))
+    }
+
+    /// Parses a RGB String into a Color
+    fn parse_rgb(s: &str) -> Option<Color> {
+        Color::parse_rgb_bytes(s.as_bytes())
+    }
+
+    /// Parses the bytes of a RGB String into a Color
+    fn parse_rgb_bytes(bytes: &[u8]) -> Option<Color> {
+        if bytes.len() == 6 {
+            let red   = Color::parse_hex_byte(&bytes[0..2])?;
+            let green = Color::parse_hex_byte(&bytes[2..4])?;
+            let blue  = Color::parse_hex_byte(&bytes[4..6])?;
+            Some(Color::rgba(red, green, blue, 255))
+        } else {
+            None
+        }
+    }
+
+    /// Parses a RGB or RGBA shorthand String into a Color, eg "f00" or "f008"
+    fn parse_short(s: &str) -> Option<Color> {
+        let bytes = s.as_bytes();
+        let digit = |index: usize| Color::hex_digit(bytes[index]).map(|digit| digit * 17);
+        match bytes.len() {
+            3 => Some(Color::rgba(digit(0)?, digit(1)?, digit(2)?, 255)),
+            4 => Some(Color::rgba(digit(0)?, digit(1)?, digit(2)?, digit(3)?)),
+            _ => None
+        }
+    }
+
+    /// Parses exactly one single color value from a String (eg "AA", "RR", "GG" or "BB")
+    fn parse_color(s: &str) -> Option<u8> {
+        Color::parse_hex_byte(s.as_bytes())
+    }
+
+    /// Parses the first two bytes as hex digits, the high one first.
+    fn parse_hex_byte(bytes: &[u8]) -> Option<u8> {
+        let high = Color::hex_digit(*bytes.first()?)?;
+        let low  = Color::hex_digit(*bytes.get(1)?)?;
+        Some((high << 4) | low)
+    }
+
+    /// Converts a hex digit into its value, using a lookup table.
+    fn hex_digit(byte: u8) -> Option<u8> {
+        match HEX_DIGITS[byte as usize] {
+            INVALID_HEX_DIGIT => None,
+            digit => Some(digit)
+        }
+    }
+
+}
+
+/// Prints the color as "#AARRGGBB", with the real (unswapped) channels.
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_hex_string(true, HexPrefix::Hash))
+    }
+}
+
+/// Colors are ordered by alpha, then red, green and blue.
+///
+/// This uses the real channels, so the order does not depend on whether
+/// red and blue are stored switched.
+impl Ord for Color {
+    fn cmp(&self, other: &Color) -> Ordering {
+        let (red, green, blue, alpha) = self.unswapped();
+        let (other_red, other_green, other_blue, other_alpha) = other.unswapped();
+        (alpha, red, green, blue).cmp(&(other_alpha, other_red, other_green, other_blue))
+    }
+}
+
+impl PartialOrd for Color {
+    fn partial_cmp(&self, other: &Color) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The default color is opaque black.
+///
+/// Note that it is not transparent, so drawing with it will not be a no-op.
+impl Default for Color {
+    fn default() -> Self {
+        Color::BLACK
+    }
+}
+
+/// Adds the channels, including the alpha channel, saturating at 255.
+///
+/// So `Color::RED + Color::GREEN` is yellow.
+impl Add for Color {
+    type Output = Color;
+
+    fn add(self, other: Color) -> Color {
+        // Both colors are swapped the same way, so the fields can be added directly
+        Color {
+            red: self.red.saturating_add(other.red),
+            green: self.green.saturating_add(other.green),
+            blue: self.blue.saturating_add(other.blue),
+            alpha: self.alpha.saturating_add(other.alpha)
+        }
+    }
+}
+
+/// Subtracts the channels, including the alpha channel, saturating at 0.
+///
+/// So `Color::WHITE - Color::RED` is cyan, but transparent, as the alpha
+/// channels are subtracted as well. Use `with_alpha` to make it opaque again.
+impl Sub for Color {
+    type Output = Color;
+
+    fn sub(self, other: Color) -> Color {
+        // Both colors are swapped the same way, so the fields can be subtracted directly
+        Color {
+            red: self.red.saturating_sub(other.red),
+            green: self.green.saturating_sub(other.green),
+            blue: self.blue.saturating_sub(other.blue),
+            alpha: self.alpha.saturating_sub(other.alpha)
+        }
+    }
+}
+
+/// Multiplies the channels, including the alpha channel, by the factor.
+///
+/// The results are rounded and clamped to 0-255, like `clamp_to_gamut` does.
+impl Mul<f32> for Color {
+    type Output = Color;
+
+    fn mul(self, factor: f32) -> Color {
+        let scale = |channel: u8| Color::clamp_channel(channel as f32 * factor);
+        Color {
+            red: scale(self.red),
+            green: scale(self.green),
+            blue: scale(self.blue),
+            alpha: scale(self.alpha)
+        }
+    }
+}
+
+impl From<u32> for Color {
+    fn from(val: u32) -> Self {
+        let red   = ((val & 0xff0000) >> 16) as u8;
+        let green = ((val & 0x00ff00) >> 8)  as u8;
+        let blue  =  (val & 0x0000ff)        as u8;
+        Color::rgba(red, green, blue, 255)
+    }
+}
+
+impl FromStr for Color {
+    type Err = ColorParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Color::try_parse(s)
+    }
+}
+
+impl From<(u8, u8, u8, u8)> for Color {
+    fn from((red, green, blue, alpha): (u8, u8, u8, u8)) -> Self {
+        Color::rgba(red, green, blue, alpha)
+    }
+}
+
+impl From<(u8, u8, u8)> for Color {
+    fn from((red, green, blue): (u8, u8, u8)) -> Self {
+        Color::rgba(red, green, blue, 255)
+    }
+}
+
+/// Decodes the raw bytes of a pixel.
+///
+/// Three bytes are red, green and blue (like a `Pixbuf` without alpha),
+/// the color is opaque.
+///
+/// Four bytes are a pixel of a Cairo `Format::ARgb32` surface, the inverse
+/// of writing `as_cairo_argb32().to_ne_bytes()`: a premultiplied `0xAARRGGBB`
+/// in native endianness (B, G, R, A on little-endian machines).
+/// The color channels are unpremultiplied.
+impl<'a> TryFrom<&'a [u8]> for Color {
+    type Error = InvalidPixelLength;
+
+    fn try_from(bytes: &'a [u8]) -> Result<Self, Self::Error> {
+        match *bytes {
+            [red, green, blue] => Ok(Color::rgba(red, green, blue, 255)),
+            [a, b, c, d] => {
+                let pixel = u32::from_ne_bytes([a, b, c, d]);
+                Ok(Color::from_argb_u32(pixel).unpremultiply())
+            },
+            _ => Err(InvalidPixelLength { got: bytes.len() })
+        }
+    }
+}
+
+/// Serializes colors as a "#AARRGGBB" string, and deserializes them from any
+/// string `Color::parse` understands or an object like `{"r": 255, "g": 0, "b": 0}`
+/// (where "a" is optional and defaults to 255).
+#[cfg(feature = "serde")]
+mod serialization {
+    use std::fmt;
+    use serde::{Serialize, Serializer, Deserialize, Deserializer};
+    use serde::de::{self, MapAccess, Visitor};
+    use super::{Color, HexPrefix};
+
+    impl Serialize for Color {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where S: Serializer
+        {
+            serializer.serialize_str(&self.to_hex_string(true, HexPrefix::Hash))
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Color {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where D: Deserializer<'de>
+        {
+            deserializer.deserialize_any(ColorVisitor)
+        }
+    }
+
+    struct ColorVisitor;
+
+    impl<'de> Visitor<'de> for ColorVisitor {
+        type Value = Color;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "a color string or an object with r, g, b and a")
+        }
+
+        fn visit_str<E>(self, value: &str) -> Result<Color, E>
+            where E: de::Error
+        {
+            Color::try_parse(value)
+                .map_err(|err| E::custom(format!("invalid color \"{}\": {}", value, err)))
+        }
+
+        fn visit_map<M>(self, mut map: M) -> Result<Color, M::Error>
+            where M: MapAccess<'de>
+        {
+            let (mut red, mut green, mut blue, mut alpha) = (None, None, None, None);
+            while let Some(key) = map.next_key::<String>()? {
+                let channel = match key.as_str() {
+                    "r" => &mut red,
+                    "g" => &mut green,
+                    "b" => &mut blue,
+                    "a" => &mut alpha,
+                    _ => return Err(de::Error::unknown_field(&key, &["r", "g", "b", "a"]))
+                };
+                if channel.is_some() {
+                    return Err(de::Error::custom(format!("duplicate field `{}`", key)))
+                }
+                *channel = Some(map.next_value::<u8>()?);
+            }
+            let red = red.ok_or_else(|| de::Error::missing_field("r"))?;
+            let green = green.ok_or_else(|| de::Error::missing_field("g"))?;
+            let blue = blue.ok_or_else(|| de::Error::missing_field("b"))?;
+            Ok(Color::rgba(red, green, blue, alpha.unwrap_or(255)))
+        }
+    }
+
+    #[cfg(test)]
+    mod test {
+        use serde_json;
+        use ::render::Color;
+
+        #[test]
+        fn serialize() {
+            let color = Color::rgba(255, 0, 0, 128);
+            assert_eq!("\"#80FF0000\"", serde_json::to_string(&color).unwrap());
+        }
+
+        #[test]
+        fn deserialize() {
+            let color = Color::rgba(255, 0, 0, 128);
+            let from_str: Color = serde_json::from_str("\"#80FF0000\"").unwrap();
+            let from_function: Color = serde_json::from_str("\"rgba(255, 0, 0, 128)\"")
+                .unwrap();
+            let from_object: Color = serde_json::from_str(r#"{"r": 255, "g": 0, "b": 0, "a": 128}"#)
+                .unwrap();
+            assert_eq!(color, from_str);
+            assert_eq!(color, from_function);
+            assert_eq!(color, from_object);
+            let opaque: Color = serde_json::from_str(r#"{"b": 0, "g": 0, "r": 255}"#).unwrap();
+            assert_eq!(Color::rgba(255, 0, 0, 255), opaque);
+            // round trip
+            let json = serde_json::to_string(&color).unwrap();
+            assert_eq!(color, serde_json::from_str::<Color>(&json).unwrap());
+            let json = color.to_json_object();
+            assert_eq!(color, serde_json::from_str::<Color>(&json).unwrap());
+        }
+
+        #[test]
+        fn deserialize_errors() {
+            let err = serde_json::from_str::<Color>("\"#ff00g0\"").unwrap_err();
+            assert!(err.to_string()
+                    .contains("the color has an invalid hex digit 'g' at position 5"));
+            assert!(serde_json::from_str::<Color>(r#"{"r": 255, "g": 0}"#).is_err());
+            assert!(serde_json::from_str::<Color>(r#"{"r": 256, "g": 0, "b": 0}"#).is_err());
+            assert!(serde_json::from_str::<Color>(r#"{"r": 0, "g": 0, "b": 0, "x": 0}"#)
+                    .is_err());
+            assert!(serde_json::from_str::<Color>("42").is_err());
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use std::cmp::Ordering;
+    use ::render::Color;
+    use super::{wlc_swap, ColorParseError, GammaMode, HexOrder, HexPrefix, InvalidPixelLength,
+                ParseMode};
+    use std::convert::TryFrom;
+    use super::super::color_names::NAMED_COLORS;
+
+    #[test]
+    #[cfg(feature = "wlc-color-swap")]
+    fn test_from_u32() {
+        let hex_red   = 0xFF0000;
+        let hex_green = 0x00FF00;
+        let hex_blue  = 0x0000FF;
+        let r: Color = hex_red.into();
+        let g: Color = hex_green.into();
+        let b: Color = hex_blue.into();
+        // test red values
+        assert_eq!(0x00, r.red);
+        assert_eq!(0x00, r.green);
+        assert_eq!(0xFF, r.blue);
+        // test green values
+        assert_eq!(0x00, g.red);
+        assert_eq!(0xFF, g.green);
+        assert_eq!(0x00, g.blue);
+        // test blue values
+        assert_eq!(0xFF, b.red);
+        assert_eq!(0x00, b.green);
+        assert_eq!(0x00, b.blue);
+    }
+
+    #[test]
+    fn parse_color() {
+        // test all numbers, uppercase and lowercase letters
+        assert_eq!(17 * 0,  Color::parse_color("00").unwrap());
+        assert_eq!(17 * 1,  Color::parse_color("11").unwrap());
+        assert_eq!(17 * 2,  Color::parse_color("22").unwrap());
+        assert_eq!(17 * 3,  Color::parse_color("33").unwrap());
+        assert_eq!(17 * 4,  Color::parse_color("44").unwrap());
+        assert_eq!(17 * 5,  Color::parse_color("55").unwrap());
+        assert_eq!(17 * 6,  Color::parse_color("66").unwrap());
+        assert_eq!(17 * 7,  Color::parse_color("77").unwrap());
+        assert_eq!(17 * 8,  Color::parse_color("88").unwrap());
+        assert_eq!(17 * 9,  Color::parse_color("99").unwrap());
+        assert_eq!(17 * 10, Color::parse_color("aa").unwrap());
+        assert_eq!(17 * 10, Color::parse_color("AA").unwrap());
+        assert_eq!(17 * 11, Color::parse_color("bb").unwrap());
+        assert_eq!(17 * 11, Color::parse_color("BB").unwrap());
+        assert_eq!(17 * 12, Color::parse_color("cc").unwrap());
+        assert_eq!(17 * 12, Color::parse_color("CC").unwrap());
+        assert_eq!(17 * 13, Color::parse_color("dd").unwrap());
+        assert_eq!(17 * 13, Color::parse_color("DD").unwrap());
+        assert_eq!(17 * 14, Color::parse_color("ee").unwrap());
+        assert_eq!(17 * 14, Color::parse_color("EE").unwrap());
+        assert_eq!(17 * 15, Color::parse_color("ff").unwrap());
+        assert_eq!(17 * 15, Color::parse_color("FF").unwrap());
+        // test a few mixed values
+        assert_eq!(00,      Color::parse_color("00").unwrap());
+        assert_eq!(50,      Color::parse_color("32").unwrap());
+        assert_eq!(100,     Color::parse_color("64").unwrap());
+        assert_eq!(150,     Color::parse_color("96").unwrap());
+        assert_eq!(200,     Color::parse_color("c8").unwrap());
+        assert_eq!(250,     Color::parse_color("fa").unwrap());
+        assert_eq!(255,     Color::parse_color("ff").unwrap());
+        // test invalid values
+        assert_eq!(false,   Color::parse_color("").is_some());
+        assert_eq!(false,   Color::parse_color("h").is_some());
+        assert_eq!(false,   Color::parse_color("h2").is_some());
+        assert_eq!(false,   Color::parse_color("yz").is_some());
+        assert_eq!(false,   Color::parse_color("3x").is_some());
+    }
+
+    #[test]
+    #[cfg(feature = "wlc-color-swap")]
+    fn parse_rgb() {
+        // test some valid color values
+        let rgb_black = Color::parse_rgb("000000").unwrap();
+        assert_eq!(0,   rgb_black.red);
+        assert_eq!(0,   rgb_black.green);
+        assert_eq!(0,   rgb_black.blue);
+        assert_eq!(255, rgb_black.alpha);
+        let rgb_red   = Color::parse_rgb("ff0000").unwrap();
+        assert_eq!(0,   rgb_red.red);
+        assert_eq!(0,   rgb_red.green);
+        assert_eq!(255, rgb_red.blue);
+        assert_eq!(255, rgb_red.alpha);
+        let rgb_green = Color::parse_rgb("00ff00").unwrap();
+        assert_eq!(0,   rgb_green.red);
+        assert_eq!(255, rgb_green.green);
+        assert_eq!(0,   rgb_green.blue);
+        assert_eq!(255, rgb_green.alpha);
+        let rgb_blue  = Color::parse_rgb("0000ff").unwrap();
+        assert_eq!(255, rgb_blue.red);
+        assert_eq!(0,   rgb_blue.green);
+        assert_eq!(0,   rgb_blue.blue);
+        assert_eq!(255, rgb_blue.alpha);
+        let rgb_white = Color::parse_rgb("ffffff").unwrap();
+        assert_eq!(255, rgb_white.red);
+        assert_eq!(255, rgb_white.green);
+        assert_eq!(255, rgb_white.blue);
+        assert_eq!(255, rgb_white.alpha);
+        // test invalid formats
         assert_eq!(false, Color::parse_rgb("").is_some());
         assert_eq!(false, Color::parse_rgb("0").is_some());
         assert_eq!(false, Color::parse_rgb("00").is_some());
@@ -221,84 +2176,1775 @@ mod test {
         assert_eq!(false, Color::parse_rgb("00000000").is_some());
     }
 
+    #[test]
+    #[cfg(feature = "wlc-color-swap")]
+    fn parse_argb() {
+        // test some valid color values
+        let rgb_transparent = Color::parse_argb("00000000").unwrap();
+        assert_eq!(0,   rgb_transparent.red);
+        assert_eq!(0,   rgb_transparent.green);
+        assert_eq!(0,   rgb_transparent.blue);
+        assert_eq!(0,   rgb_transparent.alpha);
+        let rgb_red   = Color::parse_argb("40ff0000").unwrap();
+        assert_eq!(0,   rgb_red.red);
+        assert_eq!(0,   rgb_red.green);
+        assert_eq!(255, rgb_red.blue);
+        assert_eq!(64,  rgb_red.alpha);
+        let rgb_green = Color::parse_argb("8000ff00").unwrap();
+        assert_eq!(0,   rgb_green.red);
+        assert_eq!(255, rgb_green.green);
+        assert_eq!(0,   rgb_green.blue);
+        assert_eq!(128, rgb_green.alpha);
+        let rgb_blue  = Color::parse_argb("c00000ff").unwrap();
+        assert_eq!(255, rgb_blue.red);
+        assert_eq!(0,   rgb_blue.green);
+        assert_eq!(0,   rgb_blue.blue);
+        assert_eq!(192, rgb_blue.alpha);
+        let rgb_white = Color::parse_argb("ffffffff").unwrap();
+        assert_eq!(255, rgb_white.red);
+        assert_eq!(255, rgb_white.green);
+        assert_eq!(255, rgb_white.blue);
+        assert_eq!(255, rgb_white.alpha);
+        // test some invalid formats
+        assert_eq!(false, Color::parse_argb("").is_some());
+        assert_eq!(false, Color::parse_argb("0").is_some());
+        assert_eq!(false, Color::parse_argb("00").is_some());
+        assert_eq!(false, Color::parse_argb("000").is_some());
+        assert_eq!(false, Color::parse_argb("0000").is_some());
+        assert_eq!(false, Color::parse_argb("00000").is_some());
+        assert_eq!(false, Color::parse_argb("000000").is_some());
+        assert_eq!(false, Color::parse_argb("0000000").is_some());
+        assert_eq!(false, Color::parse_argb("xxxxxxxx").is_some());
+        assert_eq!(false, Color::parse_argb("000000000").is_some());
+        assert_eq!(false, Color::parse_argb("0000000000").is_some());
+    }
+
+    #[test]
+    #[cfg(feature = "wlc-color-swap")]
+    fn parse() {
+        // #-prefixed (HTML-style)
+        assert_eq!(true, Color::parse("#000000").is_some());
+        assert_eq!(true, Color::parse("#00000000").is_some());
+        // 0x-prefixed (Hex-style)
+        assert_eq!(true, Color::parse("0x000000").is_some());
+        assert_eq!(true, Color::parse("0x00000000").is_some());
+        // No prefix
+        assert_eq!(true, Color::parse("000000").is_some());
+        assert_eq!(true, Color::parse("00000000").is_some());
+        // Shorthand
+        assert_eq!(true, Color::parse("000").is_some());
+        assert_eq!(true, Color::parse("0000").is_some());
+        // Actual colors
+        let red = Color::parse("0xFFFF0000").unwrap();
+        assert_eq!(0,   red.red);
+        assert_eq!(0,   red.green);
+        assert_eq!(255, red.blue);
+        assert_eq!(255, red.alpha);
+        let green = Color::parse("0xFF00FF00").unwrap();
+        assert_eq!(0,   green.red);
+        assert_eq!(255, green.green);
+        assert_eq!(0,   green.blue);
+        assert_eq!(255, green.alpha);
+        let blue = Color::parse("0xFF0000FF").unwrap();
+        assert_eq!(255, blue.red);
+        assert_eq!(0,   blue.green);
+        assert_eq!(0,   blue.blue);
+        assert_eq!(255, blue.alpha);
+        // wrong formats
+        assert_eq!(false, Color::parse("").is_some());
+        assert_eq!(false, Color::parse("0").is_some());
+        assert_eq!(false, Color::parse("00").is_some());
+        assert_eq!(false, Color::parse("00000").is_some());
+        assert_eq!(false, Color::parse("0000000").is_some());
+    }
+
+    #[test]
+    fn to_hex_string() {
+        let red = Color::parse("#FF0000").unwrap();
+        assert_eq!("#FF0000",    red.to_hex_string(false, HexPrefix::Hash));
+        assert_eq!("0xFF0000",   red.to_hex_string(false, HexPrefix::ZeroX));
+        assert_eq!("FF0000",     red.to_hex_string(false, HexPrefix::None));
+        assert_eq!("#FFFF0000",  red.to_hex_string(true,  HexPrefix::Hash));
+        assert_eq!("0xFFFF0000", red.to_hex_string(true,  HexPrefix::ZeroX));
+        assert_eq!("FFFF0000",   red.to_hex_string(true,  HexPrefix::None));
+        // round trip all the colors used in the parse tests
+        let colors = ["#000000", "#00000000", "0x000000", "0x00000000",
+                      "000000", "00000000", "0xFFFF0000", "0xFF00FF00",
+                      "0xFF0000FF", "40FF0000", "8000FF00", "C00000FF",
+                      "FFFFFFFF", "FF0000", "00FF00", "0000FF", "FFFFFF"];
+        for s in colors.iter() {
+            let color = Color::parse(s).unwrap();
+            let prefix = if s.starts_with("#") {
+                HexPrefix::Hash
+            } else if s.starts_with("0x") {
+                HexPrefix::ZeroX
+            } else {
+                HexPrefix::None
+            };
+            let include_alpha = s.trim_start_matches("#")
+                .trim_start_matches("0x").len() == 8;
+            assert_eq!(*s, color.to_hex_string(include_alpha, prefix));
+            let serialized = color.to_hex_string(true, HexPrefix::None);
+            assert_eq!(Some(color), Color::parse(&serialized));
+        }
+    }
+
+    #[test]
+    fn from_str() {
+        assert_eq!(Ok(Color::rgba(255, 0, 0, 255)), "#FF0000".parse::<Color>());
+        assert_eq!(Ok(Color::rgba(0, 255, 0, 128)), "0x8000FF00".parse::<Color>());
+        assert_eq!(Ok(Color::rgba(0, 0, 255, 255)), "0000ff".parse::<Color>());
+        assert_eq!(Err(ColorParseError::EmptyInput), "".parse::<Color>());
+        assert_eq!(Err(ColorParseError::InvalidLength { got: 0 }),
+                   "#".parse::<Color>());
+        assert_eq!(Err(ColorParseError::InvalidLength { got: 5 }),
+                   "#00000".parse::<Color>());
+        assert_eq!(Err(ColorParseError::InvalidLength { got: 7 }),
+                   "0x0000000".parse::<Color>());
+        assert_eq!(Err(ColorParseError::InvalidDigit { position: 1, found: 'x' }),
+                   "#xxxxxx".parse::<Color>());
+        assert_eq!(Err(ColorParseError::UnknownName), "xxxxxx".parse::<Color>());
+        assert_eq!(Err(ColorParseError::InvalidDigit { position: 5, found: 'g' }),
+                   "#ff00g0".parse::<Color>());
+    }
+
+    #[test]
+    fn try_parse() {
+        assert_eq!(Ok(Color::rgba(255, 0, 0, 255)), Color::try_parse("ff0000"));
+        // the position points at the offending character, including the prefix
+        let inputs = ["ff00g0", "#ff00g0", "0xff00g0", "0x00ff00g0", "#z0000000"];
+        for input in inputs.iter() {
+            match Color::try_parse(input) {
+                Err(ColorParseError::InvalidDigit { position, found }) => {
+                    assert_eq!(Some(found), input.chars().nth(position));
+                },
+                result => panic!("Unexpected result {:?} for {}", result, input)
+            }
+        }
+        let err = Color::try_parse("ff00g0").unwrap_err();
+        assert_eq!("the color has an invalid hex digit 'g' at position 4",
+                   format!("{}", err));
+        let err = Color::try_parse("#fff00").unwrap_err();
+        assert_eq!("the color has 5 digits, expected 3, 4, 6, 8, 12 or 16", format!("{}", err));
+        let err = Color::try_parse("").unwrap_err();
+        assert_eq!("the color is empty", format!("{}", err));
+        // misspelled names aren't reported as hex colors
+        assert_eq!(Err(ColorParseError::UnknownName), Color::try_parse("reddish"));
+        assert_eq!(Err(ColorParseError::UnknownName), Color::try_parse(" CornflowerBlu "));
+        assert_eq!(Err(ColorParseError::UnknownName), "reddish".parse::<Color>());
+        let err = Color::try_parse("reddish").unwrap_err();
+        assert_eq!("the color name is unknown", format!("{}", err));
+        // but words with a prefix or digits still are
+        assert_eq!(Err(ColorParseError::InvalidLength { got: 7 }), Color::try_parse("#reddish"));
+        assert_eq!(Err(ColorParseError::InvalidLength { got: 5 }), Color::try_parse("faded"));
+        assert_eq!(Err(ColorParseError::InvalidDigit { position: 4, found: 'z' }),
+                   Color::try_parse("bad0z0"));
+    }
+
+    #[test]
+    fn parse_rgb_function() {
+        // test some valid color values
+        assert_eq!(Some(Color::rgba(0, 0, 0, 255)),     Color::parse("rgb(0,0,0)"));
+        assert_eq!(Some(Color::rgba(255, 0, 0, 255)),   Color::parse("rgb(255, 0, 0)"));
+        assert_eq!(Some(Color::rgba(0, 255, 0, 255)),   Color::parse("rgb( 0 ,255, 0 )"));
+        assert_eq!(Some(Color::rgba(0, 0, 255, 255)),   Color::parse("rgb(0, 0, 255)"));
+        assert_eq!(Some(Color::rgba(255, 255, 255, 255)),
+                   Color::parse("rgb(255, 255, 255)"));
+        // the swap is preserved
+        assert_eq!(Color::parse("ff0000"), Color::parse("rgb(255,0,0)"));
+        // alpha as float or integer
+        assert_eq!(Some(Color::rgba(255, 0, 0, 128)), Color::parse("rgba(255,0,0,0.5)"));
+        assert_eq!(Some(Color::rgba(255, 0, 0, 255)), Color::parse("rgba(255,0,0,1.0)"));
+        assert_eq!(Some(Color::rgba(255, 0, 0, 0)),   Color::parse("rgba(255,0,0,0.0)"));
+        assert_eq!(Some(Color::rgba(255, 0, 0, 64)),  Color::parse("rgba(255, 0, 0, 64)"));
+        assert_eq!(Some(Color::rgba(0, 255, 0, 192)), Color::parse("rgba( 0, 255, 0, 192 )"));
+        // test invalid formats
+        assert_eq!(None, Color::parse("rgb()"));
+        assert_eq!(None, Color::parse("rgb(0)"));
+        assert_eq!(None, Color::parse("rgb(0,0)"));
+        assert_eq!(None, Color::parse("rgb(0,0,0,0,0)"));
+        assert_eq!(None, Color::parse("rgb(256,0,0)"));
+        assert_eq!(None, Color::parse("rgb(-1,0,0)"));
+        assert_eq!(None, Color::parse("rgb(x,0,0)"));
+        assert_eq!(None, Color::parse("rgb(0,0,0"));
+        assert_eq!(None, Color::parse("rgb 0,0,0)"));
+        assert_eq!(None, Color::parse("rgba(0,0,0,1.5)"));
+        assert_eq!(None, Color::parse("rgba(0,0,0,256)"));
+        assert_eq!(None, Color::parse("cmyk(0,0,0,0)"));
+        assert_eq!(Err(ColorParseError::InvalidFunction), Color::try_parse("rgb(0,0)"));
+    }
+
+    /// Checks that two colors differ by at most one in every channel
+    fn assert_close(expected: Color, actual: Color) {
+        let (r1, g1, b1, a1) = expected.values();
+        let (r2, g2, b2, a2) = actual.values();
+        for &(c1, c2) in [(r1, r2), (g1, g2), (b1, b2), (a1, a2)].iter() {
+            assert!((c1 as i16 - c2 as i16).abs() <= 1,
+                    "expected {:?}, got {:?}", expected, actual);
+        }
+    }
+
+    #[test]
+    fn hsl() {
+        let red   = Color::rgba(255, 0, 0, 255);
+        let green = Color::rgba(0, 255, 0, 255);
+        let blue  = Color::rgba(0, 0, 255, 255);
+        assert_eq!(red,   Color::from_hsl(0.0, 1.0, 0.5, 255));
+        assert_eq!(green, Color::from_hsl(120.0, 1.0, 0.5, 255));
+        assert_eq!(blue,  Color::from_hsl(240.0, 1.0, 0.5, 255));
+        // hue wraps around
+        assert_eq!(red,   Color::from_hsl(360.0, 1.0, 0.5, 255));
+        assert_eq!(green, Color::from_hsl(-240.0, 1.0, 0.5, 255));
+        assert_eq!((0.0, 1.0, 0.5),   red.to_hsl());
+        assert_eq!((120.0, 1.0, 0.5), green.to_hsl());
+        assert_eq!((240.0, 1.0, 0.5), blue.to_hsl());
+        // round trips, including some grays
+        let colors = [red, green, blue,
+                      Color::rgba(0, 0, 0, 255),
+                      Color::rgba(128, 128, 128, 255),
+                      Color::rgba(200, 200, 200, 255),
+                      Color::rgba(255, 255, 255, 128),
+                      Color::rgba(12, 34, 56, 78)];
+        for color in colors.iter() {
+            let (h, s, l) = color.to_hsl();
+            let (_, _, _, a) = color.values();
+            assert_close(*color, Color::from_hsl(h, s, l, a));
+        }
+        // grays have no saturation
+        let (_, s, l) = Color::rgba(128, 128, 128, 255).to_hsl();
+        assert_eq!(0.0, s);
+        assert!((l - 128.0 / 255.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn parse_hsl_function() {
+        assert_eq!(Some(Color::rgba(0, 255, 0, 255)), Color::parse("hsl(120, 100%, 50%)"));
+        assert_eq!(Some(Color::rgba(0, 255, 0, 255)), Color::parse("hsl(120,1.0,0.5)"));
+        assert_eq!(Some(Color::rgba(255, 0, 0, 255)), Color::parse("hsl(360, 100%, 50%)"));
+        assert_eq!(Some(Color::rgba(0, 0, 255, 128)),
+                   Color::parse("hsla(240, 100%, 50%, 0.5)"));
+        assert_eq!(Some(Color::rgba(255, 255, 255, 255)), Color::parse("hsl(0, 0%, 100%)"));
+        assert_eq!(None, Color::parse("hsl(0, 0%)"));
+        assert_eq!(None, Color::parse("hsl(0, 101%, 50%)"));
+        assert_eq!(None, Color::parse("hsl(0, 1.5, 0.5)"));
+        assert_eq!(None, Color::parse("hsl(red, 100%, 50%)"));
+        assert_eq!(None, Color::parse("hsl(inf, 100%, 50%)"));
+    }
+
+    #[test]
+    fn parse_named() {
+        assert_eq!(Color::parse("ff0000"), Color::parse("red"));
+        assert_eq!(Color::parse("008000"), Color::parse("green"));
+        assert_eq!(Color::parse("0000ff"), Color::parse("blue"));
+        assert_eq!(Color::parse("6495ed"), Color::parse("cornflowerblue"));
+        assert_eq!(Color::parse("6495ed"), Color::parse("CornflowerBlue"));
+        assert_eq!(Color::parse("ffffff"), Color::parse("WHITE"));
+        assert_eq!(Some(Color::rgba(0, 0, 0, 0)), Color::parse("transparent"));
+        assert_eq!(Some(Color::rgba(0, 0, 0, 0)), Color::parse("Transparent"));
+        assert_eq!(None, Color::parse("blurple"));
+        assert_eq!(None, Color::parse("#red"));
+        assert_eq!(None, Color::parse("0xred"));
+        // the lookup is a binary search, so the table must stay sorted
+        for pair in NAMED_COLORS.windows(2) {
+            assert!(pair[0].0 < pair[1].0, "{} is out of order", pair[1].0);
+        }
+        for &(name, value) in NAMED_COLORS.iter() {
+            assert_eq!(Some(Color::from(value)), Color::parse(name));
+        }
+    }
+
+    #[test]
+    fn blend_over() {
+        let white = Color::rgba(255, 255, 255, 255);
+        let blue = Color::rgba(0, 0, 255, 255);
+        let half_red = Color::rgba(255, 0, 0, 128);
+        let transparent = Color::rgba(0, 0, 0, 0);
+        // 50% red over white is pink
+        assert_eq!(Color::rgba(255, 127, 127, 255), half_red.blend_over(&white));
+        assert_eq!(Color::rgba(128, 0, 127, 255), half_red.blend_over(&blue));
+        // opaque colors replace the background
+        assert_eq!(blue, blue.blend_over(&white));
+        assert_eq!(blue, blue.blend_over(&half_red));
+        // transparent colors leave the background untouched
+        assert_eq!(white, transparent.blend_over(&white));
+        assert_eq!(half_red, transparent.blend_over(&half_red));
+        assert_eq!(transparent, transparent.blend_over(&transparent));
+        // the result is only as opaque as both colors together
+        let (_, _, _, alpha) = half_red.blend_over(&half_red).values();
+        assert_eq!(192, alpha);
+    }
+
+    #[test]
+    fn lighten_darken() {
+        let colors = [Color::rgba(255, 0, 0, 255),
+                      Color::rgba(12, 34, 56, 78),
+                      Color::rgba(128, 128, 128, 128),
+                      Color::rgba(0, 0, 0, 255),
+                      Color::rgba(255, 255, 255, 0)];
+        for color in colors.iter() {
+            let (_, _, _, alpha) = color.values();
+            assert_eq!(Color::rgba(0, 0, 0, alpha), color.darken(1.0));
+            assert_eq!(Color::rgba(255, 255, 255, alpha), color.lighten(1.0));
+            assert_eq!(*color, color.lighten(0.0));
+            assert_eq!(*color, color.darken(0.0));
+        }
+        // red has a lightness of 50%
+        let red = Color::rgba(255, 0, 0, 255);
+        assert_eq!(Color::rgba(255, 102, 102, 255), red.lighten(0.2));
+        assert_eq!(Color::rgba(153, 0, 0, 255), red.darken(0.2));
+        // out of range amounts are clamped
+        assert_eq!(red.lighten(1.0), red.lighten(2.0));
+        assert_eq!(red, red.lighten(-1.0));
+    }
+
+    #[test]
+    fn saturate_desaturate() {
+        let colors = [Color::rgba(255, 0, 0, 255),
+                      Color::rgba(12, 34, 56, 78),
+                      Color::rgba(200, 150, 100, 255),
+                      Color::rgba(128, 128, 128, 128)];
+        for color in colors.iter() {
+            let gray = color.desaturate(1.0);
+            assert_eq!(gray.red(), gray.green());
+            assert_eq!(gray.green(), gray.blue());
+            assert_eq!(color.alpha(), gray.alpha());
+            // it is as hue neutral as the result of grayscale
+            assert_eq!(gray, gray.grayscale());
+            assert_eq!(*color, color.saturate(0.0));
+            assert_eq!(*color, color.desaturate(0.0));
+        }
+        // red is already fully saturated
+        let red = Color::rgba(255, 0, 0, 255);
+        assert_eq!(red, red.saturate(0.5));
+        assert_eq!(Color::rgba(191, 64, 64, 255), red.desaturate(0.5));
+        assert_eq!(red, red.desaturate(0.5).saturate(0.5));
+        assert_eq!(Color::rgba(128, 128, 128, 255), red.desaturate(1.0));
+        // saturating a gray does nothing, as it has no hue
+        let gray = Color::rgba(100, 100, 100, 200);
+        assert_eq!(gray, gray.saturate(1.0));
+        // out of range amounts are clamped
+        assert_eq!(red.desaturate(1.0), red.desaturate(2.0));
+        assert_eq!(red.desaturate(0.5), red.desaturate(0.5).saturate(-1.0));
+    }
+
+    #[test]
+    fn rotate_hue() {
+        let red = Color::rgba(255, 0, 0, 255);
+        assert_close(Color::rgba(0, 255, 0, 255), red.rotate_hue(120.0));
+        assert_close(Color::rgba(0, 0, 255, 255), red.rotate_hue(240.0));
+        assert_close(Color::rgba(0, 255, 255, 255), red.rotate_hue(180.0));
+        assert_close(red.rotate_hue(240.0), red.rotate_hue(-120.0));
+        assert_close(red.rotate_hue(120.0), red.rotate_hue(480.0));
+        let colors = [red,
+                      Color::rgba(12, 34, 56, 78),
+                      Color::rgba(200, 150, 100, 255),
+                      Color::rgba(128, 128, 128, 128)];
+        for color in colors.iter() {
+            assert_close(*color, color.rotate_hue(360.0));
+            assert_close(*color, color.rotate_hue(0.0));
+            assert_eq!(color.alpha(), color.rotate_hue(90.0).alpha());
+        }
+        // grays have no hue to rotate
+        let gray = Color::rgba(128, 128, 128, 128);
+        assert_eq!(gray, gray.rotate_hue(123.0));
+    }
+
+    #[test]
+    fn interpolate() {
+        let black = Color::rgba(0, 0, 0, 255);
+        let white = Color::rgba(255, 255, 255, 255);
+        let half_red = Color::rgba(255, 0, 0, 128);
+        assert_eq!(black, black.interpolate(&white, 0.0));
+        assert_eq!(white, black.interpolate(&white, 1.0));
+        assert_eq!(half_red, half_red.interpolate(&white, 0.0));
+        assert_eq!(white, half_red.interpolate(&white, 1.0));
+        let (r, g, b, a) = black.interpolate(&white, 0.5).values();
+        assert!(r == 127 || r == 128);
+        assert!(g == 127 || g == 128);
+        assert!(b == 127 || b == 128);
+        assert_eq!(255, a);
+        // alpha is interpolated too, and red/blue are kept apart
+        assert_eq!(Color::rgba(255, 0, 64, 160),
+                   half_red.interpolate(&Color::rgba(255, 0, 255, 255), 0.25));
+        // t is clamped
+        assert_eq!(black, black.interpolate(&white, -1.0));
+        assert_eq!(white, black.interpolate(&white, 2.0));
+    }
+
+    #[test]
+    fn default() {
+        let color = Color::default();
+        // black looks the same with or without red and blue switched
+        assert_eq!((0, 0, 0, 255), color.values());
+        assert_eq!(Color::parse("#FF000000"), Some(color));
+    }
+
+    #[test]
+    fn premultiply() {
+        let half_white = Color::rgba(255, 255, 255, 128);
+        assert_eq!(Color::rgba(128, 128, 128, 128), half_white.premultiply());
+        assert_eq!(half_white, half_white.premultiply().unpremultiply());
+        // red and blue are kept apart
+        assert_eq!(Color::rgba(128, 64, 0, 128),
+                   Color::rgba(255, 128, 0, 128).premultiply());
+        assert_eq!(Color::rgba(0, 0, 0, 0), Color::rgba(255, 0, 0, 0).premultiply());
+        assert_eq!(Color::rgba(0, 0, 0, 0), Color::rgba(255, 0, 0, 0).unpremultiply());
+        // opaque colors are not changed
+        for value in 0..256 {
+            let value = value as u8;
+            let color = Color::rgba(value, 255 - value, value / 2, 255);
+            assert_eq!(color, color.premultiply());
+            assert_eq!(color, color.premultiply().unpremultiply());
+        }
+    }
+
+    #[test]
+    fn premultiply_rounding() {
+        // rounded to the nearest, not truncated
+        assert_eq!(Color::rgba(1, 1, 0, 128), Color::rgba(1, 2, 0, 128).premultiply());
+        assert_eq!(Color::rgba(2, 0, 0, 3), Color::rgba(200, 0, 0, 3).premultiply());
+        assert_eq!(Color::rgba(254, 0, 0, 254), Color::rgba(255, 0, 0, 254).premultiply());
+        for alpha in 0..=255u32 {
+            // the error that can't be avoided with this few bits
+            let max_error = if alpha == 0 { 255 } else { 255_u32.div_ceil(2 * alpha) };
+            assert!(alpha < 128 || max_error == 1);
+            for channel in 0..=255u32 {
+                let color = Color::rgba(channel as u8, 0, 255 - channel as u8, alpha as u8);
+                let premultiplied = color.premultiply();
+                assert_eq!(((channel * alpha) as f32 / 255.0).round() as u8, premultiplied.red());
+                assert!(premultiplied.is_valid_premultiplied());
+                let (red, _, blue, _) = premultiplied.unpremultiply().unswapped();
+                let error = (red as u32).abs_diff(channel).max((blue as u32).abs_diff(255 - channel));
+                assert!(error <= max_error, "{:?} is off by {}", color, error);
+            }
+        }
+    }
+
+    #[test]
+    fn as_cairo_argb32() {
+        assert_eq!(0xFFFF0000, Color::rgba(255, 0, 0, 255).as_cairo_argb32());
+        assert_eq!(0xFF00FF00, Color::rgba(0, 255, 0, 255).as_cairo_argb32());
+        assert_eq!(0xFF0000FF, Color::rgba(0, 0, 255, 255).as_cairo_argb32());
+        assert_eq!(0xFF123456, Color::rgba(0x12, 0x34, 0x56, 255).as_cairo_argb32());
+        // the channels are premultiplied
+        assert_eq!(0x80808080, Color::rgba(255, 255, 255, 128).as_cairo_argb32());
+        assert_eq!(0x80800000, Color::rgba(255, 0, 0, 128).as_cairo_argb32());
+        assert_eq!(0x00000000, Color::rgba(255, 255, 255, 0).as_cairo_argb32());
+    }
+
+    #[test]
+    fn from_tuple() {
+        assert_eq!(Color::parse("ff0000").unwrap(), Color::from((255, 0, 0)));
+        assert_eq!(Color::parse("0000ff").unwrap(), Color::from((0, 0, 255)));
+        assert_eq!(Color::parse("80ff0000").unwrap(), Color::from((255, 0, 0, 128)));
+        assert_eq!(Color::parse("400000ff").unwrap(), Color::from((0, 0, 255, 64)));
+        let color: Color = (1, 2, 3).into();
+        assert_eq!(Color::rgba(1, 2, 3, 255), color);
+    }
+
+    #[test]
+    fn with_alpha() {
+        let red = Color::parse("ff0000").unwrap();
+        assert_eq!(Color::rgba(255, 0, 0, 128), red.with_alpha(128));
+        assert_eq!(Color::rgba(255, 0, 0, 0), red.with_alpha(0));
+        assert_eq!(red, red.with_alpha(128).with_alpha(255));
+        assert_eq!(Color::parse("#40123456"), Color::parse("#123456").map(|c| c.with_alpha(0x40)));
+    }
+
+    #[test]
+    fn channel_getters() {
+        let red = Color::parse("ff0000").unwrap();
+        assert_eq!(255, red.red());
+        assert_eq!(0,   red.green());
+        assert_eq!(0,   red.blue());
+        assert_eq!(255, red.alpha());
+        if cfg!(feature = "wlc-color-swap") {
+            // the stored value is still switched
+            assert_eq!(0,   red.red);
+            assert_eq!(255, red.blue);
+        }
+        let color = Color::parse("#12345678").unwrap();
+        assert_eq!(0x34, color.red());
+        assert_eq!(0x56, color.green());
+        assert_eq!(0x78, color.blue());
+        assert_eq!(0x12, color.alpha());
+    }
+
+    #[test]
+    fn parse_short() {
+        assert_eq!(Color::parse("#ff0000"), Color::parse("#f00"));
+        assert_eq!(Color::parse("#ff0000"), Color::parse("f00"));
+        assert_eq!(Color::parse("0xff0000"), Color::parse("0xf00"));
+        assert_eq!(Color::parse("#112233"), Color::parse("#123"));
+        // the alpha comes last, like in CSS
+        assert_eq!(Color::parse("#88ff0000"), Color::parse("#f008"));
+        assert_eq!(Some(Color::rgba(255, 0, 0, 0x88)), Color::parse("#f008"));
+        assert_eq!(Some(Color::rgba(0x11, 0x22, 0x33, 0x44)), Color::parse("1234"));
+        // invalid lengths and digits
+        assert_eq!(None, Color::parse("#f"));
+        assert_eq!(None, Color::parse("#f0"));
+        assert_eq!(None, Color::parse("#f0000"));
+        assert_eq!(None, Color::parse("#fg0"));
+        assert_eq!(None, Color::parse("#f00g"));
+    }
+
+    #[test]
+    fn contrast_ratio() {
+        let black = Color::rgba(0, 0, 0, 255);
+        let white = Color::rgba(255, 255, 255, 255);
+        assert!((black.contrast_ratio(&white) - 21.0).abs() < 0.01);
+        assert!((white.contrast_ratio(&black) - 21.0).abs() < 0.01);
+        assert!((white.contrast_ratio(&white) - 1.0).abs() < 0.01);
+        // alpha is ignored
+        assert!((black.with_alpha(0).contrast_ratio(&white) - 21.0).abs() < 0.01);
+        // red on white is around 4.0
+        let red = Color::rgba(255, 0, 0, 255);
+        assert!((red.contrast_ratio(&white) - 4.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn best_text_color() {
+        let black = Color::rgba(0, 0, 0, 255);
+        let white = Color::rgba(255, 255, 255, 255);
+        assert_eq!(white, black.best_text_color());
+        assert_eq!(white, Color::parse("#000080").unwrap().best_text_color());
+        assert_eq!(white, Color::parse("#333333").unwrap().best_text_color());
+        assert_eq!(black, white.best_text_color());
+        assert_eq!(black, Color::parse("#ffff00").unwrap().best_text_color());
+        assert_eq!(black, Color::parse("#cccccc").unwrap().best_text_color());
+    }
+
+    #[test]
+    fn grayscale() {
+        let red   = Color::rgba(255, 0, 0, 255).grayscale();
+        let green = Color::rgba(0, 255, 0, 255).grayscale();
+        let blue  = Color::rgba(0, 0, 255, 128).grayscale();
+        assert_eq!(Color::rgba(54, 54, 54, 255), red);
+        assert_eq!(Color::rgba(182, 182, 182, 255), green);
+        assert_eq!(Color::rgba(18, 18, 18, 128), blue);
+        assert!(green.red() > red.red() && red.red() > blue.red());
+        // grays are not changed
+        for value in 0..256 {
+            let gray = Color::rgba(value as u8, value as u8, value as u8, 200);
+            assert_eq!(gray, gray.grayscale());
+        }
+    }
+
+    #[test]
+    fn ordering() {
+        let mut colors = vec![Color::rgba(0, 0, 255, 255),
+                              Color::rgba(255, 0, 0, 255),
+                              Color::rgba(0, 255, 0, 255),
+                              Color::rgba(255, 255, 255, 0),
+                              Color::rgba(0, 0, 1, 255),
+                              Color::rgba(0, 0, 0, 128)];
+        colors.sort();
+        assert_eq!(vec![Color::rgba(255, 255, 255, 0),
+                        Color::rgba(0, 0, 0, 128),
+                        Color::rgba(0, 0, 1, 255),
+                        Color::rgba(0, 0, 255, 255),
+                        Color::rgba(0, 255, 0, 255),
+                        Color::rgba(255, 0, 0, 255)],
+                   colors);
+        assert!(Color::rgba(1, 0, 0, 255) > Color::rgba(0, 0, 255, 255));
+        assert_eq!(Ordering::Equal, Color::rgba(1, 2, 3, 4).cmp(&Color::rgba(1, 2, 3, 4)));
+    }
+
+    #[test]
+    fn parse_many() {
+        assert_eq!(Ok(vec![Color::rgba(255, 255, 255, 255),
+                           Color::rgba(0, 0, 0, 255),
+                           Color::rgba(0, 255, 0, 255)]),
+                   Color::parse_many("#fff, #000 0xFF00FF00"));
+        assert_eq!(Ok(vec![]), Color::parse_many(""));
+        assert_eq!(Ok(vec![]), Color::parse_many(" , "));
+        // trailing separators
+        assert_eq!(Ok(vec![Color::rgba(255, 0, 0, 255)]), Color::parse_many("red,"));
+        assert_eq!(Ok(vec![Color::rgba(255, 0, 0, 255)]), Color::parse_many(" red , \n"));
+        // functional notations are not split up
+        assert_eq!(Ok(vec![Color::rgba(1, 2, 3, 255), Color::rgba(255, 0, 0, 255)]),
+                   Color::parse_many("rgb(1, 2, 3), rgb(255,0,0)"));
+        // the index of the bad color is reported
+        assert_eq!(Err(ColorParseError::InvalidEntry {
+                       index: 2,
+                       reason: Box::new(ColorParseError::InvalidDigit {
+                           position: 5,
+                           found: 'g'
+                       })
+                   }),
+                   Color::parse_many("#fff #000, #ff00g0, #fff"));
+        let err = Color::parse_many("#fff #12345").unwrap_err();
+        assert_eq!("color number 1 is invalid: \
+                    the color has 5 digits, expected 3, 4, 6, 8, 12 or 16",
+                   format!("{}", err));
+    }
+
+    #[test]
+    fn parse_hex_prefix() {
+        let red = Some(Color::rgba(255, 0, 0, 255));
+        assert_eq!(red, Color::parse("0xFF0000"));
+        assert_eq!(red, Color::parse("0XFF0000"));
+        assert_eq!(red, Color::parse("0Xff0000"));
+        assert_eq!(red, Color::parse("0XFFFF0000"));
+        assert_eq!(Ok(Color::rgba(255, 0, 0, 255)), Color::try_parse("0Xff0000"));
+        assert_eq!(Err(ColorParseError::InvalidDigit { position: 3, found: 'g' }),
+                   Color::try_parse("0Xfg0000"));
+        // after the prefix, the length decides the format
+        assert_eq!(Color::parse("#00ff"), Color::parse("0x00ff"));
+        assert_eq!(Color::parse("#00ff"), Color::parse("0X00ff"));
+        // there is no prefix without the zero
+        assert_eq!(None, Color::parse("xFF0000"));
+        assert_eq!(None, Color::parse("XFF0000"));
+    }
+
+    #[test]
+    fn invert() {
+        let black = Color::rgba(0, 0, 0, 255);
+        let white = Color::rgba(255, 255, 255, 255);
+        assert_eq!(white, black.invert());
+        assert_eq!(black, white.invert());
+        assert_eq!(Color::rgba(0, 255, 255, 128), Color::rgba(255, 0, 0, 128).invert());
+        assert_eq!(Color::rgba(237, 203, 169, 0), Color::rgba(18, 52, 86, 0).invert());
+        let color = Color::rgba(18, 52, 86, 120);
+        assert_eq!(color, color.invert().invert());
+    }
+
+    #[test]
+    fn mix() {
+        let red = Color::rgba(255, 0, 0, 255);
+        let green = Color::rgba(0, 255, 0, 255);
+        let blue = Color::rgba(0, 0, 255, 255);
+        assert_eq!(Some(Color::rgba(85, 85, 85, 255)),
+                   Color::mix(&[(red, 1.0), (green, 1.0), (blue, 1.0)]));
+        // the weights are normalized
+        assert_eq!(Color::mix(&[(red, 1.0), (blue, 1.0)]),
+                   Color::mix(&[(red, 0.25), (blue, 0.25)]));
+        // the weights bias the result
+        assert_eq!(Some(Color::rgba(191, 0, 64, 255)),
+                   Color::mix(&[(red, 3.0), (blue, 1.0)]));
+        assert_eq!(Some(red), Color::mix(&[(red, 1.0), (blue, 0.0)]));
+        // alpha is averaged too
+        assert_eq!(Some(Color::rgba(255, 0, 0, 128)),
+                   Color::mix(&[(red, 1.0), (red.with_alpha(0), 1.0)]));
+        assert_eq!(None, Color::mix(&[]));
+        assert_eq!(None, Color::mix(&[(red, 0.0), (blue, 0.0)]));
+        assert_eq!(None, Color::mix(&[(red, 1.0), (blue, -1.0)]));
+        assert_eq!(None, Color::mix(&[(red, f32::NAN)]));
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!("#FFFF0000", format!("{}", Color::parse("ff0000").unwrap()));
+        assert_eq!("#800000FF", format!("{}", Color::rgba(0, 0, 255, 128)));
+        assert_eq!("#00000000", format!("{}", Color::rgba(0, 0, 0, 0)));
+        assert_eq!("#FF123456", Color::parse("#123456").unwrap().to_string());
+    }
+
+    #[test]
+    fn parse_whitespace() {
+        let red = Some(Color::rgba(255, 0, 0, 255));
+        assert_eq!(red, Color::parse(" #ff0000 "));
+        assert_eq!(red, Color::parse("\t0xff0000\n"));
+        assert_eq!(red, Color::parse("  red"));
+        assert_eq!(red, Color::parse(" rgb( 255, 0, 0 ) "));
+        assert_eq!(None, Color::parse("ff 0000"));
+        assert_eq!(None, Color::parse("ff 000"));
+        assert_eq!(None, Color::parse("# ff0000"));
+        assert_eq!(None, Color::parse("rgb (255, 0, 0)"));
+        assert_eq!(Err(ColorParseError::EmptyInput), Color::try_parse("  "));
+        // the position is still the one in the original string
+        assert_eq!(Err(ColorParseError::InvalidDigit { position: 4, found: ' ' }),
+                   Color::try_parse("  ff 000 "));
+    }
+
+    proptest! {
+        #[test]
+        fn parse_never_panics(ref s in ".*") {
+            Color::parse(s);
+        }
+
+        #[test]
+        fn parsers_never_panic_on_multibyte(ref s in "[ #0xX:/,()%.a-fA-F0-9rgbhwslé€😀]{0,20}") {
+            Color::parse(s);
+            Color::parse_cached(s);
+            let _ = Color::try_parse(s);
+            let _ = Color::parse_many(s);
+            let _ = Color::parse_css_color4(s);
+            Color::parse_with(s, ParseMode::Strict);
+            Color::parse_with_order(s, HexOrder::Rgba);
+        }
+
+        #[test]
+        fn parse_round_trips(ref s in "[ #0xXa-fA-Frgbhsl(),.%0-9]{0,24}") {
+            if let Some(color) = Color::parse(s) {
+                let serialized = color.to_hex_string(true, HexPrefix::Hash);
+                prop_assert_eq!(Ok(color), Color::try_parse(&serialized));
+            } else {
+                prop_assert!(Color::try_parse(s).is_err());
+            }
+        }
+
+        #[test]
+        fn parse_valid_hex(ref s in "(#|0x|0X)?([0-9a-fA-F]{6}|[0-9a-fA-F]{8})") {
+            let color = Color::parse(s);
+            prop_assert!(color.is_some(), "{} did not parse", s);
+            let digits = s.trim_start_matches('#')
+                .trim_start_matches("0x")
+                .trim_start_matches("0X");
+            let include_alpha = digits.len() == 8;
+            prop_assert_eq!(digits.to_uppercase(),
+                            color.unwrap().to_hex_string(include_alpha, HexPrefix::None));
+        }
+    }
+
+    #[test]
+    fn opacity_predicates() {
+        assert!(Color::rgba(255, 0, 0, 255).is_opaque());
+        assert!(!Color::rgba(255, 0, 0, 254).is_opaque());
+        assert!(!Color::rgba(255, 0, 0, 0).is_opaque());
+        assert!(Color::rgba(255, 0, 0, 0).is_transparent());
+        assert!(!Color::rgba(255, 0, 0, 1).is_transparent());
+        assert!(!Color::rgba(0, 0, 0, 255).is_transparent());
+    }
+
+    #[test]
+    fn scale_alpha() {
+        let color = Color::rgba(255, 0, 0, 200);
+        assert_eq!(Color::rgba(255, 0, 0, 100), color.scale_alpha(0.5));
+        assert_eq!(Color::rgba(255, 0, 0, 0), color.scale_alpha(0.0));
+        assert_eq!(color, color.scale_alpha(1.0));
+        // the factor is clamped
+        assert_eq!(color, color.scale_alpha(2.0));
+        assert_eq!(Color::rgba(255, 0, 0, 0), color.scale_alpha(-1.0));
+        assert_eq!(Color::rgba(0, 0, 255, 128), Color::rgba(0, 0, 255, 255).scale_alpha(0.5));
+    }
+
+    #[test]
+    fn hsv() {
+        // reference values
+        let references = [((0.0, 0.0, 0.0), Color::rgba(0, 0, 0, 255)),
+                          ((0.0, 0.0, 1.0), Color::rgba(255, 255, 255, 255)),
+                          ((0.0, 1.0, 1.0), Color::rgba(255, 0, 0, 255)),
+                          ((120.0, 1.0, 1.0), Color::rgba(0, 255, 0, 255)),
+                          ((240.0, 1.0, 1.0), Color::rgba(0, 0, 255, 255)),
+                          ((60.0, 1.0, 1.0), Color::rgba(255, 255, 0, 255)),
+                          ((180.0, 1.0, 0.5), Color::rgba(0, 128, 128, 255)),
+                          ((300.0, 0.5, 0.5), Color::rgba(128, 64, 128, 255)),
+                          ((0.0, 0.0, 0.5), Color::rgba(128, 128, 128, 255))];
+        for &((h, s, v), color) in references.iter() {
+            assert_close(color, Color::from_hsv(h, s, v, 255));
+            let (h2, s2, v2) = color.to_hsv();
+            assert!((h - h2).abs() < 1.0 && (s - s2).abs() < 0.01 && (v - v2).abs() < 0.01,
+                    "{:?} is ({}, {}, {})", color, h2, s2, v2);
+        }
+        // hue wraps around
+        assert_eq!(Color::from_hsv(0.0, 1.0, 1.0, 255), Color::from_hsv(360.0, 1.0, 1.0, 255));
+        assert_eq!(Color::from_hsv(240.0, 1.0, 1.0, 255), Color::from_hsv(-120.0, 1.0, 1.0, 255));
+        // round trips
+        let colors = [Color::rgba(12, 34, 56, 78), Color::rgba(200, 100, 50, 255)];
+        for color in colors.iter() {
+            let (h, s, v) = color.to_hsv();
+            assert_close(*color, Color::from_hsv(h, s, v, color.alpha()));
+        }
+    }
+
+    #[test]
+    fn hwb() {
+        // reference values
+        let references = [((0.0, 0.0, 0.0), Color::rgba(255, 0, 0, 255)),
+                          ((0.0, 1.0, 0.0), Color::rgba(255, 255, 255, 255)),
+                          ((0.0, 0.0, 1.0), Color::rgba(0, 0, 0, 255)),
+                          ((120.0, 0.0, 0.5), Color::rgba(0, 128, 0, 255)),
+                          ((240.0, 0.2, 0.2), Color::rgba(51, 51, 204, 255)),
+                          ((60.0, 0.4, 0.1), Color::rgba(230, 230, 102, 255)),
+                          ((0.0, 0.5, 0.5), Color::rgba(128, 128, 128, 255))];
+        for &((h, w, b), color) in references.iter() {
+            assert_close(color, Color::from_hwb(h, w, b, 255));
+            let (h2, w2, b2) = color.to_hwb();
+            assert!((h - h2).abs() < 1.0 && (w - w2).abs() < 0.01 && (b - b2).abs() < 0.01,
+                    "{:?} is ({}, {}, {})", color, h2, w2, b2);
+        }
+        // whiteness and blackness of 100% or more give a gray
+        assert_eq!(Color::rgba(128, 128, 128, 255), Color::from_hwb(90.0, 0.6, 0.6, 255));
+        assert_eq!(Color::rgba(191, 191, 191, 255), Color::from_hwb(90.0, 0.9, 0.3, 255));
+        // hue wraps around
+        assert_eq!(Color::from_hwb(240.0, 0.1, 0.1, 255), Color::from_hwb(-120.0, 0.1, 0.1, 255));
+        // round trips
+        let colors = [Color::rgba(12, 34, 56, 78), Color::rgba(200, 100, 50, 255),
+                      Color::rgba(90, 90, 90, 255)];
+        for color in colors.iter() {
+            let (h, w, b) = color.to_hwb();
+            assert_close(*color, Color::from_hwb(h, w, b, color.alpha()));
+        }
+    }
+
+    #[test]
+    fn parse_hwb_function() {
+        assert_eq!(Some(Color::rgba(0, 128, 0, 255)), Color::parse("hwb(120 0% 50%)"));
+        assert_eq!(Some(Color::rgba(51, 51, 204, 128)), Color::parse("hwb(240deg 20% 20% / 0.5)"));
+        assert_eq!(Some(Color::rgba(128, 128, 128, 255)), Color::parse(" hwb(0 70% 70%) "));
+        assert_eq!(None, Color::parse("hwb(120, 0%, 50%)"));
+        assert_eq!(None, Color::parse("hwb(120 0 0.5)"));
+        assert_eq!(None, Color::parse("hwb(120 0%)"));
+        assert_eq!(None, Color::parse("hwb(120 0% 150%)"));
+    }
+
+    #[test]
+    fn parse_hsv_function() {
+        assert_eq!(Some(Color::rgba(0, 255, 0, 255)), Color::parse("hsv(120, 100%, 100%)"));
+        assert_eq!(Some(Color::rgba(0, 255, 0, 255)), Color::parse("hsv(120, 1.0, 1.0)"));
+        assert_eq!(Some(Color::rgba(0, 0, 255, 128)),
+                   Color::parse("hsva(240, 100%, 100%, 0.5)"));
+        assert_eq!(Some(Color::rgba(128, 128, 128, 255)), Color::parse("hsv(0, 0%, 50%)"));
+        assert_eq!(None, Color::parse("hsv(0, 100%)"));
+        assert_eq!(None, Color::parse("hsv(0, 100%, 150%)"));
+    }
+
+    #[test]
+    fn gamma_modes() {
+        let black = Color::rgba(0, 0, 0, 255);
+        let white = Color::rgba(255, 255, 255, 255);
+        let srgb = black.interpolate_with(&white, 0.5, GammaMode::Srgb);
+        let linear = black.interpolate_with(&white, 0.5, GammaMode::Linear);
+        assert_eq!(black.interpolate(&white, 0.5), srgb);
+        assert_eq!(Color::rgba(128, 128, 128, 255), srgb);
+        // 50% linear light is about 73% in sRGB
+        assert_eq!(Color::rgba(188, 188, 188, 255), linear);
+        // the end points are the same in both modes
+        assert_eq!(black, black.interpolate_with(&white, 0.0, GammaMode::Linear));
+        assert_eq!(white, black.interpolate_with(&white, 1.0, GammaMode::Linear));
+        // alpha is not gamma corrected
+        let transparent = Color::rgba(0, 0, 0, 0);
+        assert_eq!(128, transparent.interpolate_with(&black, 0.5, GammaMode::Linear).alpha());
+        // blending works the same way
+        let half_white = white.with_alpha(128);
+        assert_eq!(half_white.blend_over(&black),
+                   half_white.blend_over_with(&black, GammaMode::Srgb));
+        assert_eq!(Color::rgba(128, 128, 128, 255),
+                   half_white.blend_over_with(&black, GammaMode::Srgb));
+        assert_eq!(Color::rgba(188, 188, 188, 255),
+                   half_white.blend_over_with(&black, GammaMode::Linear));
+        // opaque colors still replace the background
+        let red = Color::rgba(255, 0, 0, 255);
+        assert_eq!(red, red.blend_over_with(&white, GammaMode::Linear));
+        // every channel survives the round trip through linear light
+        for value in 0..256 {
+            let value = value as u8;
+            assert_eq!(value, GammaMode::Linear.encode(GammaMode::Linear.decode(value)));
+        }
+    }
+
+    #[test]
+    fn from_argb_u32() {
+        let color = Color::from_argb_u32(0x80FF0000);
+        assert_eq!(Color::rgba(255, 0, 0, 128), color);
+        assert_eq!(128, color.alpha());
+        assert_eq!(255, color.red());
+        assert_eq!(0, color.blue());
+        assert_eq!(Color::rgba(0x12, 0x34, 0x56, 0), Color::from_argb_u32(0x00123456));
+        assert_eq!(Color::rgba(0x12, 0x34, 0x56, 0xFF), Color::from_argb_u32(0xFF123456));
+        // From<u32> still ignores the alpha byte
+        assert_eq!(Color::rgba(255, 0, 0, 255), Color::from(0x80FF0000));
+    }
+
+    #[test]
+    fn to_argb_u32() {
+        assert_eq!(0x80FF0000, Color::rgba(255, 0, 0, 128).to_argb_u32());
+        assert_eq!(0xFF0000FF, Color::rgba(0, 0, 255, 255).to_argb_u32());
+        for &value in &[0x00000000, 0xFFFFFFFF, 0x80FF0000, 0x12345678, 0xFF00FF00, 0x01020304] {
+            assert_eq!(value, Color::from_argb_u32(value).to_argb_u32());
+        }
+    }
+
+    #[test]
+    fn parse_rgb_percentages() {
+        assert_eq!(Color::parse("rgb(255,0,0)"), Color::parse("rgb(100%,0%,0%)"));
+        assert_eq!(Some(Color::rgba(128, 255, 0, 255)), Color::parse("rgb(50%, 100%, 0%)"));
+        assert_eq!(Some(Color::rgba(26, 0, 0, 128)), Color::parse("rgba(10%, 0%, 0%, 0.5)"));
+        assert_eq!(Some(Color::rgba(3, 0, 0, 255)), Color::parse("rgb(1.2%, 0%, 0%)"));
+        // mixing integers and percentages is allowed
+        assert_eq!(Some(Color::rgba(255, 128, 0, 255)), Color::parse("rgb(100%, 128, 0)"));
+        // out of range and malformed percentages are rejected
+        assert_eq!(None, Color::parse("rgb(101%, 0%, 0%)"));
+        assert_eq!(None, Color::parse("rgb(-1%, 0%, 0%)"));
+        assert_eq!(None, Color::parse("rgb(%, 0%, 0%)"));
+        assert_eq!(None, Color::parse("rgb(50%%, 0%, 0%)"));
+        assert_eq!(None, Color::parse("rgb(NaN%, 0%, 0%)"));
+    }
+
+    #[test]
+    fn distance() {
+        let red = Color::rgba(255, 0, 0, 255);
+        let almost_red = Color::rgba(250, 5, 0, 255);
+        let blue = Color::rgba(0, 0, 255, 255);
+        assert_eq!(0.0, red.distance(&red));
+        assert_eq!(0.0, red.distance(&red.with_alpha(0)));
+        assert!(red.distance(&almost_red) < red.distance(&blue));
+        assert!(red.distance(&almost_red) < 5.0);
+        assert_eq!(red.distance(&blue), blue.distance(&red));
+        // black and white are 100 apart in lightness
+        let black = Color::rgba(0, 0, 0, 255);
+        let white = Color::rgba(255, 255, 255, 255);
+        assert!((black.distance(&white) - 100.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn constants() {
+        assert_eq!(Color::parse("#000000"), Some(Color::BLACK));
+        assert_eq!(Color::parse("#ffffff"), Some(Color::WHITE));
+        assert_eq!(Color::parse("#ff0000"), Some(Color::RED));
+        assert_eq!(Color::parse("#00ff00"), Some(Color::GREEN));
+        assert_eq!(Color::parse("#0000ff"), Some(Color::BLUE));
+        assert_eq!(Color::parse("#00000000"), Some(Color::TRANSPARENT));
+        // rgba can be used in constants
+        const ORANGE: Color = Color::rgba(255, 165, 0, 255);
+        assert_eq!(Color::parse("orange"), Some(ORANGE));
+        assert_eq!(255, Color::RED.red());
+        assert_eq!(0, Color::RED.blue());
+    }
+
+    #[test]
+    fn ansi16() {
+        assert_eq!(1, Color::rgba(205, 0, 0, 255).to_ansi16());
+        assert_eq!(1, Color::rgba(180, 20, 10, 255).to_ansi16());
+        assert_eq!(9, Color::rgba(255, 0, 0, 255).to_ansi16());
+        assert_eq!(0, Color::rgba(10, 10, 10, 255).to_ansi16());
+        assert_eq!(15, Color::rgba(250, 250, 250, 0).to_ansi16());
+        assert_eq!(Color::rgba(205, 0, 0, 255), Color::from_ansi16(1));
+        assert_eq!(Color::rgba(255, 0, 0, 255), Color::from_ansi16(9));
+        for index in 0..16 {
+            assert_eq!(index, Color::from_ansi16(index).to_ansi16());
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn ansi16_out_of_range() {
+        Color::from_ansi16(16);
+    }
+
+    #[test]
+    fn parse_hue_units() {
+        assert_eq!(Color::parse("hsl(180,100%,50%)"), Color::parse("hsl(0.5turn,100%,50%)"));
+        assert_eq!(Color::parse("hsl(180,100%,50%)"), Color::parse("hsl(180deg, 100%, 50%)"));
+        assert_eq!(Color::parse("hsv(120,1,1)"), Color::parse("hsv(120deg,1,1)"));
+        assert_eq!(Color::parse("hsla(270, 50%, 50%, 0.5)"),
+                   Color::parse("hsla(0.75turn, 50%, 50%, 0.5)"));
+        assert_eq!(Some(Color::rgba(0, 255, 255, 255)), Color::parse("hsl(-0.5turn,100%,50%)"));
+        // unknown or malformed units are rejected
+        assert_eq!(None, Color::parse("hsl(3rad,100%,50%)"));
+        assert_eq!(None, Color::parse("hsl(180 deg,100%,50%)"));
+        assert_eq!(None, Color::parse("hsl(deg,100%,50%)"));
+        assert_eq!(None, Color::parse("hsl(1turndeg,100%,50%)"));
+        assert_eq!(None, Color::parse("hsl(1e39turn,100%,50%)"));
+    }
+
+    #[test]
+    fn hex_digit_table() {
+        // the lookup table agrees with `char::to_digit`, which was used before
+        let to_digit = |byte: u8| (byte as char).to_digit(16).map(|digit| digit as u8);
+        for high in 0..256 {
+            for low in 0..256 {
+                let (high, low) = (high as u8, low as u8);
+                let expected = to_digit(high).and_then(|high| {
+                    to_digit(low).map(|low| (high << 4) | low)
+                });
+                assert_eq!(expected, Color::parse_hex_byte(&[high, low]));
+            }
+        }
+        assert_eq!(None, Color::parse_hex_byte(b"f"));
+        assert_eq!(None, Color::parse_hex_byte(&[]));
+    }
+
+    #[test]
+    fn clamp_channel() {
+        assert_eq!(128, Color::clamp_channel(127.5));
+        assert_eq!(128, Color::clamp_channel(128.5));
+        assert_eq!(127, Color::clamp_channel(127.4));
+        assert_eq!(128, Color::clamp_channel(127.6));
+        assert_eq!(0, Color::clamp_channel(0.5));
+        assert_eq!(2, Color::clamp_channel(1.5));
+        assert_eq!(254, Color::clamp_channel(254.5));
+        assert_eq!(255, Color::clamp_channel(254.51));
+        assert_eq!(255, Color::clamp_channel(255.5));
+        assert_eq!(255, Color::clamp_channel(300.0));
+        assert_eq!(255, Color::clamp_channel(f32::INFINITY));
+        assert_eq!(0, Color::clamp_channel(-0.4));
+        assert_eq!(0, Color::clamp_channel(-0.5));
+        assert_eq!(0, Color::clamp_channel(-20.0));
+        assert_eq!(0, Color::clamp_channel(f32::NEG_INFINITY));
+        assert_eq!(0, Color::clamp_channel(f32::NAN));
+        for channel in 0..256 {
+            assert_eq!(channel as u8, Color::clamp_channel(channel as f32));
+        }
+    }
+
+    #[test]
+    fn clamp_to_gamut() {
+        assert_eq!(Color::rgba(12, 34, 56, 78), Color::clamp_to_gamut(12.0, 34.0, 56.0, 78.0));
+        assert_eq!(Color::rgba(255, 0, 128, 0), Color::clamp_to_gamut(300.0, -4.0, 127.5, 0.5));
+        assert_eq!(Color::rgba(255, 255, 255, 255),
+                   Color::clamp_to_gamut(255.0, 255.0, 255.0, 255.0));
+    }
+
+    #[test]
+    fn try_from_bytes() {
+        assert_eq!(Ok(Color::rgba(255, 0, 0, 255)), Color::try_from(&[255, 0, 0][..]));
+        assert_eq!(Ok(Color::rgba(1, 2, 3, 255)), Color::try_from(&[1, 2, 3][..]));
+        // four bytes undo writing a color to a Cairo surface
+        let colors = [Color::rgba(255, 0, 0, 255),
+                      Color::rgba(12, 34, 56, 255),
+                      Color::TRANSPARENT];
+        for color in colors.iter() {
+            let bytes = color.as_cairo_argb32().to_ne_bytes();
+            assert_eq!(Ok(*color), Color::try_from(&bytes[..]));
+        }
+        // some precision is lost to premultiplying, all of it if transparent
+        let bytes = Color::rgba(0, 0, 255, 0).as_cairo_argb32().to_ne_bytes();
+        assert_eq!(Ok(Color::TRANSPARENT), Color::try_from(&bytes[..]));
+        let bytes = Color::rgba(255, 100, 0, 128).as_cairo_argb32().to_ne_bytes();
+        assert_close(Color::rgba(255, 100, 0, 128), Color::try_from(&bytes[..]).unwrap());
+        if cfg!(target_endian = "little") {
+            assert_eq!(Ok(Color::rgba(255, 0, 0, 255)), Color::try_from(&[0, 0, 255, 255][..]));
+        }
+        assert_eq!(Err(InvalidPixelLength { got: 5 }), Color::try_from(&[0, 0, 0, 0, 0][..]));
+        assert_eq!(Err(InvalidPixelLength { got: 0 }), Color::try_from(&[][..]));
+        assert_eq!("the pixel has 2 bytes, expected 3 or 4",
+                   Color::try_from(&[0, 0][..]).unwrap_err().to_string());
+    }
+
+    #[test]
+    fn float_channels() {
+        assert_eq!((1.0, 1.0, 1.0, 1.0), Color::WHITE.to_cairo_rgba());
+        assert_eq!((0.0, 0.0, 0.0, 0.0), Color::TRANSPARENT.to_cairo_rgba());
+        // the channels are unswapped
+        assert_eq!((1.0, 0.0, 0.0, 1.0), Color::RED.to_cairo_rgba());
+        assert_eq!((0.0, 0.0, 1.0, 1.0), Color::BLUE.to_cairo_rgba());
+        let color = Color::rgba(255, 51, 0, 102);
+        assert_eq!(1.0, color.red_f32());
+        assert_eq!(0.2, color.green_f32());
+        assert_eq!(0.0, color.blue_f32());
+        assert_eq!(0.4, color.alpha_f32());
+    }
+
+    #[test]
+    fn parse_modes() {
+        let red = Some(Color::rgba(255, 0, 0, 255));
+        let half_red = Some(Color::rgba(255, 0, 0, 128));
+        assert_eq!(red, Color::parse_with("#ff0000", ParseMode::Strict));
+        assert_eq!(red, Color::parse_with("#FF0000", ParseMode::Strict));
+        assert_eq!(half_red, Color::parse_with("#80ff0000", ParseMode::Strict));
+        for &s in &["0xff0000", "0x80ff0000", "ff0000", "80ff0000", " #ff0000",
+                    "#f00", "#f008", "red", "rgb(255, 0, 0)", "#0xff0000", "##ff0000"] {
+            assert_eq!(None, Color::parse_with(s, ParseMode::Strict), "{}", s);
+            assert_eq!(Color::parse(s), Color::parse_with(s, ParseMode::Lenient), "{}", s);
+        }
+        assert_eq!(red, Color::parse_with("0xff0000", ParseMode::Lenient));
+        assert_eq!(red, Color::parse_with("ff0000", ParseMode::Lenient));
+        assert_eq!(half_red, Color::parse_with("80ff0000", ParseMode::Lenient));
+        assert_eq!(None, Color::parse_with("#ff00", ParseMode::Strict));
+        assert_eq!(None, Color::parse_with("#ff000g", ParseMode::Strict));
+        assert_eq!(None, Color::parse_with("#ffé00", ParseMode::Strict));
+    }
+
+    #[test]
+    fn blend_modes() {
+        let a = Color::rgba(200, 100, 50, 255);
+        let b = Color::rgba(128, 255, 0, 64);
+        let c = Color::rgba(10, 128, 255, 128);
+        let d = Color::rgba(64, 192, 32, 255);
+        assert_eq!(Color::rgba(100, 100, 0, 255), a.blend_multiply(&b));
+        assert_eq!(Color::rgba(3, 96, 32, 128), c.blend_multiply(&d));
+        assert_eq!(Color::rgba(228, 255, 50, 255), a.blend_screen(&b));
+        assert_eq!(Color::rgba(71, 224, 255, 128), c.blend_screen(&d));
+        assert_eq!(Color::rgba(200, 255, 0, 255), a.blend_overlay(&b));
+        assert_eq!(Color::rgba(5, 192, 64, 128), c.blend_overlay(&d));
+        // the neutral colors of multiply and screen
+        assert_eq!(a, a.blend_multiply(&Color::WHITE));
+        assert_eq!(a, a.blend_screen(&Color::BLACK));
+        assert_eq!(Color::BLACK, a.blend_multiply(&Color::BLACK));
+        assert_eq!(Color::WHITE, a.blend_screen(&Color::WHITE));
+        // multiply and screen are commutative, apart from alpha
+        assert_eq!(a.blend_multiply(&b), b.blend_multiply(&a).with_alpha(255));
+        assert_eq!(a.blend_screen(&b), b.blend_screen(&a).with_alpha(255));
+    }
+
+    #[test]
+    fn valid_premultiplied() {
+        assert!(Color::rgba(64, 128, 0, 128).is_valid_premultiplied());
+        assert!(Color::rgba(255, 128, 0, 255).premultiply().is_valid_premultiplied());
+        assert!(Color::TRANSPARENT.is_valid_premultiplied());
+        assert!(!Color::rgba(64, 129, 0, 128).is_valid_premultiplied());
+        assert!(!Color::rgba(1, 0, 0, 0).is_valid_premultiplied());
+        assert!(!Color::rgba(0, 0, 1, 0).is_valid_premultiplied());
+        let invalid = Color::rgba(200, 100, 0, 128);
+        assert_eq!(Color::rgba(128, 100, 0, 128), invalid.fix_premultiplied());
+        assert!(invalid.fix_premultiplied().is_valid_premultiplied());
+        // valid colors are kept
+        let valid = Color::rgba(64, 128, 0, 128);
+        assert_eq!(valid, valid.fix_premultiplied());
+    }
+
+    #[test]
+    fn wlc_swap_is_self_inverse() {
+        let values = [(0, 0, 0, 0), (255, 0, 0, 255), (1, 2, 3, 4), (12, 34, 56, 78),
+                      (255, 255, 255, 255), (0, 128, 255, 64)];
+        for &(red, green, blue, alpha) in values.iter() {
+            let (r, g, b, a) = wlc_swap(red, green, blue, alpha);
+            assert_eq!((red, green, blue, alpha), wlc_swap(r, g, b, a));
+            // only red and blue may be switched
+            assert_eq!((green, alpha), (g, a));
+            let color = Color::rgba(red, green, blue, alpha);
+            assert_eq!((r, g, b, a), color.values());
+            assert_eq!((red, green, blue, alpha), color.unswapped());
+        }
+        if cfg!(feature = "wlc-color-swap") {
+            assert_eq!((3, 2, 1, 4), wlc_swap(1, 2, 3, 4));
+        } else {
+            assert_eq!((1, 2, 3, 4), wlc_swap(1, 2, 3, 4));
+        }
+    }
+
+    #[test]
+    fn average_with() {
+        let transparent = Color::rgba(0, 0, 0, 0);
+        let white = Color::rgba(255, 255, 255, 255);
+        assert_eq!(Color::rgba(128, 128, 128, 128), transparent.average_with(&white));
+        assert_eq!(Color::rgba(128, 128, 128, 128), white.average_with(&transparent));
+        assert_eq!(white, white.average_with(&white));
+        assert_eq!(Color::rgba(3, 20, 100, 255),
+                   Color::rgba(2, 10, 0, 255).average_with(&Color::rgba(3, 30, 200, 255)));
+        // the same as interpolating, except for halves
+        let a = Color::rgba(12, 34, 56, 78);
+        let b = Color::rgba(98, 76, 54, 32);
+        assert_eq!(a.interpolate(&b, 0.5), a.average_with(&b));
+        assert_eq!(Color::rgba(2, 0, 0, 0).interpolate(&Color::rgba(3, 0, 0, 0), 0.5).red(), 2);
+        assert_eq!(Color::rgba(2, 0, 0, 0).average_with(&Color::rgba(3, 0, 0, 0)).red(), 3);
+    }
+
+    #[test]
+    fn to_json_object() {
+        assert_eq!(r#"{"r":255,"g":0,"b":0,"a":255}"#, Color::RED.to_json_object());
+        assert_eq!(r#"{"r":12,"g":34,"b":56,"a":0}"#,
+                   Color::rgba(12, 34, 56, 0).to_json_object());
+    }
+
+    #[test]
+    fn parse_deep() {
+        assert_eq!(Color::parse("#ff0000"), Color::parse("#ffff00000000"));
+        assert_eq!(Color::parse("#ff0000"), Color::parse("0xFFFF00000000"));
+        assert_eq!(Color::parse("#80ff0000"), Color::parse("#8000ffff00000000"));
+        assert_eq!(Some(Color::rgba(128, 127, 1, 255)), Color::parse("#80007fff01ff"));
+        assert_eq!(Some(Color::rgba(18, 86, 154, 0)), Color::parse("00ff123456789abc"));
+        // the dropped digits have to be valid as well
+        assert_eq!(None, Color::parse("#ff0g00000000"));
+        assert_eq!(None, Color::parse("#ffff0000000g"));
+        assert_eq!(Err(ColorParseError::InvalidDigit { position: 4, found: 'g' }),
+                   Color::try_parse("#ff0g00000000"));
+        // other lengths are still rejected
+        for &s in &["#fff00000000", "#ffff000000000", "#ffff00000000000", "#ffff000000000000f"] {
+            assert_eq!(None, Color::parse(s));
+            let got = s.len() - 1;
+            assert_eq!(Err(ColorParseError::InvalidLength { got }), Color::try_parse(s));
+        }
+    }
+
+    #[test]
+    fn name() {
+        assert_eq!(Some("red"), Color::rgba(255, 0, 0, 255).name());
+        assert_eq!(Some("cornflowerblue"), Color::parse("#6495ED").unwrap().name());
+        assert_eq!(Some("aqua"), Color::parse("cyan").unwrap().name());
+        assert_eq!(Some("transparent"), Color::TRANSPARENT.name());
+        assert_eq!(None, Color::rgba(255, 0, 1, 255).name());
+        assert_eq!(None, Color::rgba(255, 0, 0, 254).name());
+        assert_eq!(None, Color::rgba(255, 255, 255, 0).name());
+        // every name maps back to a color with that name
+        for &(name, _) in NAMED_COLORS {
+            let color = Color::parse(name).unwrap();
+            assert_eq!(Some(color), Color::parse(color.name().unwrap()));
+        }
+    }
+
+    #[test]
+    fn lerp_hsl() {
+        let red = Color::rgba(255, 0, 0, 255);
+        let green = Color::rgba(0, 255, 0, 255);
+        let blue = Color::rgba(0, 0, 255, 255);
+        let yellow = red.lerp_hsl(&green, 0.5);
+        assert_close(Color::rgba(255, 255, 0, 255), yellow);
+        // unlike the muddy midpoint in RGB, it is as light as red and green
+        assert_eq!(Color::rgba(128, 128, 0, 255), red.interpolate(&green, 0.5));
+        assert!((yellow.to_hsl().2 - 0.5).abs() < 0.01);
+        // the shorter way from blue (240) to red (360) is through magenta
+        assert_close(Color::rgba(255, 0, 255, 255), blue.lerp_hsl(&red, 0.5));
+        assert_close(Color::rgba(255, 0, 255, 255), red.lerp_hsl(&blue, 0.5));
+        assert_eq!(red, red.lerp_hsl(&green, 0.0));
+        assert_close(green, red.lerp_hsl(&green, 1.0));
+        assert_eq!(red.lerp_hsl(&green, 1.0), red.lerp_hsl(&green, 2.0));
+        // grays keep the hue of the other color
+        let white = Color::rgba(255, 255, 255, 255);
+        assert_close(Color::rgba(223, 159, 159, 255), red.lerp_hsl(&white, 0.5));
+        assert_close(Color::rgba(223, 159, 159, 255), white.lerp_hsl(&red, 0.5));
+        // alpha is interpolated linearly
+        assert_eq!(128, red.with_alpha(0).lerp_hsl(&green, 0.5).alpha());
+    }
+
+    #[test]
+    fn parse_hex_lengths() {
+        let red = Some(Color::rgba(255, 0, 0, 255));
+        let half_red = Some(Color::rgba(255, 0, 0, 136));
+        for &prefix in &["", "#", "0x", "0X", "##", "#0x"] {
+            let parse = |digits: &str| Color::parse(&format!("{}{}", prefix, digits));
+            assert_eq!(red, parse("f00"));
+            assert_eq!(half_red, parse("f008"));
+            assert_eq!(red, parse("ff0000"));
+            assert_eq!(half_red, parse("88ff0000"));
+            assert_eq!(red, parse("ff0000000000"));
+            assert_eq!(half_red, parse("8800ff0000000000"));
+            for len in (0..20).filter(|len| ![3, 4, 6, 8, 12, 16].contains(len)) {
+                assert_eq!(None, parse(&"f".repeat(len)));
+            }
+        }
+    }
+
+    #[test]
+    fn parse_css_color4() {
+        let red = Ok(Color::rgba(255, 0, 0, 255));
+        let half_red = Ok(Color::rgba(255, 0, 0, 128));
+        // hex, with the alpha last
+        assert_eq!(red, Color::parse_css_color4("#f00"));
+        assert_eq!(Ok(Color::rgba(255, 0, 0, 136)), Color::parse_css_color4("#f008"));
+        assert_eq!(red, Color::parse_css_color4("  #FF0000 "));
+        assert_eq!(half_red, Color::parse_css_color4("#ff000080"));
+        // names
+        assert_eq!(red, Color::parse_css_color4("Red"));
+        assert_eq!(Ok(Color::TRANSPARENT), Color::parse_css_color4("transparent"));
+        // rgb, with commas and with spaces
+        assert_eq!(red, Color::parse_css_color4("rgb(255, 0, 0)"));
+        assert_eq!(red, Color::parse_css_color4("rgb(100%, 0%, 0%)"));
+        assert_eq!(half_red, Color::parse_css_color4("rgba(255, 0, 0, 0.5)"));
+        assert_eq!(half_red, Color::parse_css_color4("rgb(255, 0, 0, 50%)"));
+        assert_eq!(red, Color::parse_css_color4("rgb(255 0 0)"));
+        assert_eq!(half_red, Color::parse_css_color4("rgb(255 0 0 / 50%)"));
+        assert_eq!(half_red, Color::parse_css_color4("RGBA(255  0 0/0.5)"));
+        // in CSS an alpha of 1 is opaque, not 1/255
+        assert_eq!(red, Color::parse_css_color4("rgb(255 0 0 / 1)"));
+        // hsl
+        assert_eq!(red, Color::parse_css_color4("hsl(0, 100%, 50%)"));
+        assert_eq!(red, Color::parse_css_color4("hsl(360deg 100% 50%)"));
+        assert_eq!(half_red, Color::parse_css_color4("hsla(1turn 100% 50% / 0.5)"));
+        // hwb
+        assert_eq!(red, Color::parse_css_color4("hwb(0 0% 0%)"));
+        assert_eq!(half_red, Color::parse_css_color4("hwb(0 0% 0% / 50%)"));
+        assert_eq!(Ok(Color::rgba(128, 128, 128, 255)), Color::parse_css_color4("hwb(0 50% 50%)"));
+        // errors
+        assert_eq!(Err(ColorParseError::EmptyInput), Color::parse_css_color4("  "));
+        assert_eq!(Err(ColorParseError::InvalidLength { got: 5 }),
+                   Color::parse_css_color4("#ff000"));
+        assert_eq!(Err(ColorParseError::InvalidDigit { position: 3, found: 'x' }),
+                   Color::parse_css_color4(" #0x0000"));
+        assert_eq!(Err(ColorParseError::UnknownName), Color::parse_css_color4("reddish"));
+        assert_eq!(Err(ColorParseError::UnknownName), Color::parse_css_color4("ff0000"));
+        assert_eq!(Err(ColorParseError::UnknownName), Color::parse_css_color4("0xff0000"));
+        for &s in &["rgb(255, 0 0)", "rgb(255 0 0, 1)", "rgb(255, 0, 0 / 1)", "rgb(255 0 0 /)",
+                    "rgb(255 0 0 / 1 1)", "rgb(255 0)", "rgb(255 0 0 0)", "rgb(256 0 0)",
+                    "hsl(0 1 0.5)", "hwb(0, 0%, 0%)", "hsv(0 100% 100%)", "rgb(255 0 0",
+                    "rgb(255 0 0 / 2)"] {
+            assert_eq!(Err(ColorParseError::InvalidFunction), Color::parse_css_color4(s), "{}", s);
+        }
+    }
+
+    #[test]
+    fn luminance() {
+        assert_eq!(0.0, Color::BLACK.luminance());
+        assert!((1.0 - Color::WHITE.luminance()).abs() < 1e-6);
+        assert_eq!(Color::BLACK.luminance(), Color::TRANSPARENT.luminance());
+        let (red, green, blue) = (Color::RED.luminance(), Color::GREEN.luminance(),
+                                  Color::BLUE.luminance());
+        assert!(green > red && green > blue, "{} {} {}", red, green, blue);
+        assert!((0.2126 - red).abs() < 1e-6);
+        assert!((0.0722 - blue).abs() < 1e-6);
+        // gray 128 is about 21.6% as bright as white in linear light
+        assert!((0.2158 - Color::rgba(128, 128, 128, 255).luminance()).abs() < 0.001);
+    }
+
+    #[test]
+    fn alpha_blend_onto() {
+        let blue = Color::rgba(0, 0, 255, 255).as_cairo_argb32().to_ne_bytes();
+        let mut pixel = blue;
+        assert_eq!(Ok(()), Color::rgba(255, 0, 0, 128).alpha_blend_onto(&mut pixel));
+        assert_eq!(0xFF80007F_u32.to_ne_bytes(), pixel);
+        assert_eq!(Ok(Color::rgba(128, 0, 127, 255)), Color::try_from(&pixel[..]));
+        assert_eq!(Color::rgba(255, 0, 0, 128).blend_over(&Color::rgba(0, 0, 255, 255)),
+                   Color::try_from(&pixel[..]).unwrap());
+        // transparent colors change nothing, opaque ones replace the pixel
+        let mut pixel = blue;
+        Color::TRANSPARENT.alpha_blend_onto(&mut pixel).unwrap();
+        assert_eq!(blue, pixel);
+        Color::rgba(1, 2, 3, 255).alpha_blend_onto(&mut pixel).unwrap();
+        assert_eq!(0xFF010203_u32.to_ne_bytes(), pixel);
+        // onto a transparent pixel, the premultiplied color is written
+        let mut pixel = [0; 4];
+        Color::rgba(255, 0, 0, 128).alpha_blend_onto(&mut pixel).unwrap();
+        assert_eq!(0x80800000_u32.to_ne_bytes(), pixel);
+        // only works on a single pixel, eg not on a whole row
+        let mut row = [0xAB; 8];
+        assert_eq!(Err(InvalidPixelLength { got: 8 }), Color::RED.alpha_blend_onto(&mut row));
+        assert_eq!(Err(InvalidPixelLength { got: 3 }), Color::RED.alpha_blend_onto(&mut row[..3]));
+        assert_eq!([0xAB; 8], row);
+    }
+
+    #[test]
+    fn rgb565() {
+        assert_eq!(0xF800, Color::rgba(255, 0, 0, 255).to_rgb565());
+        assert_eq!(0x07E0, Color::rgba(0, 255, 0, 255).to_rgb565());
+        assert_eq!(0x001F, Color::rgba(0, 0, 255, 255).to_rgb565());
+        assert_eq!(0xFFFF, Color::rgba(255, 255, 255, 0).to_rgb565());
+        assert_eq!(0x0000, Color::BLACK.to_rgb565());
+        assert_eq!(Color::rgba(255, 0, 0, 255), Color::from_rgb565(0xF800));
+        assert_eq!(Color::rgba(0, 255, 0, 255), Color::from_rgb565(0x07E0));
+        assert_eq!(Color::rgba(0, 0, 255, 255), Color::from_rgb565(0x001F));
+        assert_eq!(Color::rgba(132, 130, 132, 255), Color::from_rgb565(0x8410));
+        // every 16 bit value survives the round trip
+        for val in 0..=u16::MAX {
+            assert_eq!(val, Color::from_rgb565(val).to_rgb565());
+        }
+        // and colors are within the precision of the channels
+        for channel in 0..=255 {
+            let color = Color::rgba(channel, channel, 255 - channel, 255);
+            let (red, green, blue, _) = Color::from_rgb565(color.to_rgb565()).unswapped();
+            assert!((red as i32 - channel as i32).abs() <= 4, "{:?}", color);
+            assert!((green as i32 - channel as i32).abs() <= 2, "{:?}", color);
+            assert!((blue as i32 - (255 - channel) as i32).abs() <= 4, "{:?}", color);
+        }
+    }
+
+    #[test]
+    fn is_grayscale() {
+        assert!(Color::BLACK.is_grayscale());
+        assert!(Color::WHITE.is_grayscale());
+        assert!(Color::TRANSPARENT.is_grayscale());
+        assert!(Color::rgba(128, 128, 128, 7).is_grayscale());
+        assert!(Color::RED.grayscale().is_grayscale());
+        assert!(!Color::RED.is_grayscale());
+        assert!(!Color::rgba(128, 128, 129, 255).is_grayscale());
+        assert!(!Color::rgba(127, 128, 128, 255).is_grayscale());
+    }
+
+    #[test]
+    fn parse_with_order() {
+        let argb = Some(Color::rgba(0x22, 0x33, 0x44, 0x11));
+        let rgba = Some(Color::rgba(0x11, 0x22, 0x33, 0x44));
+        assert_eq!(argb, Color::parse_with_order("#11223344", HexOrder::Argb));
+        assert_eq!(rgba, Color::parse_with_order("#11223344", HexOrder::Rgba));
+        assert_eq!(rgba, Color::parse_with_order(" 0x11223344 ", HexOrder::Rgba));
+        assert_eq!(rgba, Color::parse_with_order("11223344", HexOrder::Rgba));
+        assert_eq!(rgba, Color::parse_with_order("#1100220033004400", HexOrder::Rgba));
+        assert_eq!(rgba, Color::parse_with_order("#11223344;", HexOrder::Rgba));
+        assert_eq!(rgba, Color::parse_with_order(" #11223344 ; ", HexOrder::Rgba));
+        assert_eq!(Color::parse("#11223344"), Color::parse_with_order("#11223344", HexOrder::Argb));
+        // the other formats are the same in both orders
+        for &s in &["#112233", "#1234", "red", "rgba(1, 2, 3, 0.5)", "#111122223333"] {
+            assert_eq!(Color::parse(s), Color::parse_with_order(s, HexOrder::Argb), "{}", s);
+            assert_eq!(Color::parse(s), Color::parse_with_order(s, HexOrder::Rgba), "{}", s);
+        }
+        assert_eq!(None, Color::parse_with_order("#1122334", HexOrder::Rgba));
+        assert_eq!(None, Color::parse_with_order("#1122334g", HexOrder::Rgba));
+    }
+
+    #[test]
+    fn from_temperature() {
+        let daylight = Color::from_temperature(6500.0);
+        assert_close(Color::rgba(255, 254, 250, 255), daylight);
+        let (red, green, blue, _) = Color::from_temperature(2000.0).unswapped();
+        assert!(red == 255 && red > green && green > blue, "{} {} {}", red, green, blue);
+        let (red, green, blue, _) = Color::from_temperature(15000.0).unswapped();
+        assert!(blue == 255 && blue > green && green > red, "{} {} {}", red, green, blue);
+        // warmer is redder
+        assert!(Color::from_temperature(3000.0).blue() < Color::from_temperature(4000.0).blue());
+        // clamped
+        assert_eq!(Color::from_temperature(1000.0), Color::from_temperature(0.0));
+        assert_eq!(Color::from_temperature(1000.0), Color::from_temperature(-500.0));
+        assert_eq!(Color::from_temperature(40000.0), Color::from_temperature(1e9));
+        assert_eq!(Color::rgba(255, 68, 0, 255), Color::from_temperature(1000.0));
+    }
+
+    #[test]
+    fn with_channel() {
+        assert_eq!(Color::parse("ff0000"), Color::parse("000000").map(|c| c.with_red(255)));
+        assert_eq!(Color::parse("00ff00"), Color::parse("000000").map(|c| c.with_green(255)));
+        assert_eq!(Color::parse("0000ff"), Color::parse("000000").map(|c| c.with_blue(255)));
+        let color = Color::rgba(1, 2, 3, 4);
+        assert_eq!(Color::rgba(9, 2, 3, 4), color.with_red(9));
+        assert_eq!(Color::rgba(1, 9, 3, 4), color.with_green(9));
+        assert_eq!(Color::rgba(1, 2, 9, 4), color.with_blue(9));
+        assert_eq!(9, color.with_red(9).red());
+        assert_eq!(9, color.with_blue(9).blue());
+        assert_eq!(Color::rgba(5, 6, 7, 8),
+                   color.with_red(5).with_green(6).with_blue(7).with_alpha(8));
+    }
+
+    #[test]
+    fn parse_cached() {
+        let inputs = ["#1a2b3c", " 0xff1a2b3c", "1a2b3c", "red", "hsla(210, 50%, 40%, 0.8)", "nope", "", " red "];
+        for _ in 0..2 {
+            for &s in &inputs {
+                assert_eq!(Color::parse(s), Color::parse_cached(s), "{}", s);
+            }
+        }
+        // filling up the cache doesn't change the results
+        for i in 0..(2 * super::PARSE_CACHE_CAPACITY) {
+            let s = format!("{:06x}", i);
+            assert_eq!(Color::parse(&s), Color::parse_cached(&s));
+            assert_eq!(Color::parse(&s), Color::parse_cached(&s));
+        }
+        for &s in &inputs {
+            assert_eq!(Color::parse(s), Color::parse_cached(s), "{}", s);
+        }
+    }
+
+    #[test]
+    fn logical_eq() {
+        let colors = [Color::RED, Color::BLUE, Color::TRANSPARENT, Color::rgba(1, 2, 3, 4),
+                      Color::rgba(3, 2, 1, 4), Color::rgba(1, 2, 3, 5)];
+        for a in colors.iter() {
+            for b in colors.iter() {
+                assert_eq!(a == b, a.logical_eq(b), "{:?} {:?}", a, b);
+            }
+        }
+        assert!(Color::parse("#ff0000").unwrap().logical_eq(&Color::rgba(255, 0, 0, 255)));
+        assert!(Color::from_argb_u32(0x80102030).logical_eq(&Color::rgba(16, 32, 48, 128)));
+        // the stored fields are swapped, the channels are not
+        let (red, green, blue, alpha) = wlc_swap(16, 32, 48, 128);
+        let stored = Color { red, green, blue, alpha };
+        assert!(stored.logical_eq(&Color::rgba(16, 32, 48, 128)));
+        assert_eq!((16, 32, 48, 128), stored.unswapped());
+        assert!(!Color::RED.logical_eq(&Color::BLUE));
+    }
+
+    #[test]
+    fn parse_function_without_commas() {
+        let half_red = Some(Color::rgba(255, 0, 0, 128));
+        assert_eq!(half_red, Color::parse("rgb(255 0 0 / 50%)"));
+        assert_eq!(half_red, Color::parse("rgb(255 0 0 / 0.5)"));
+        assert_eq!(half_red, Color::parse("rgba(255 0 0/0.5)"));
+        assert_eq!(half_red, Color::parse("  rgb( 100%  0% 0% / 50% ) "));
+        assert_eq!(Some(Color::RED), Color::parse("rgb(255 0 0)"));
+        assert_eq!(Some(Color::RED), Color::parse("rgb(255 0 0 / 1)"));
+        assert_eq!(Some(Color::rgba(0, 0, 255, 128)), Color::parse("hsl(240 100% 50% / 50%)"));
+        // the comma form keeps its alpha
+        assert_eq!(Some(Color::rgba(255, 0, 0, 1)), Color::parse("rgb(255, 0, 0, 1)"));
+        for &s in &["rgb(255 0 0 0.5)", "rgb(255 0 0 /)", "rgb(255, 0, 0 / 0.5)",
+                    "rgb(255 0, 0)", "rgb(255 0 0 / 150%)", "hsv(0 100% 100%)",
+                    "RGB(255 0 0)", "rgb()"] {
+            assert_eq!(None, Color::parse(s), "{}", s);
+        }
+    }
+
+    #[test]
+    fn quantize() {
+        for channel in 0..=255 {
+            let color = Color::rgba(channel, 255 - channel, channel / 3, channel);
+            for &levels in &[0, 1, 2] {
+                let (red, green, blue, alpha) = color.quantize(levels).unswapped();
+                for &quantized in &[red, green, blue] {
+                    assert!(quantized == 0 || quantized == 255, "{:?}", color);
+                }
+                assert_eq!(channel, alpha);
+            }
+            // quantized colors stay the same
+            let quantized = color.quantize(5);
+            assert_eq!(quantized, quantized.quantize(5));
+        }
+        assert_eq!(Color::rgba(0, 255, 255, 100), Color::rgba(127, 128, 200, 100).quantize(2));
+        assert_eq!(Color::rgba(0, 128, 255, 255), Color::rgba(63, 64, 192, 255).quantize(3));
+        assert_eq!(Color::rgba(85, 170, 255, 255), Color::rgba(90, 160, 240, 255).quantize(4));
+    }
+
+    #[test]
+    fn operators() {
+        assert_eq!(Color::rgba(255, 255, 0, 255), Color::RED + Color::GREEN);
+        assert_eq!(Color::rgba(0, 255, 255, 0), Color::WHITE - Color::RED);
+        assert_eq!(Color::rgba(128, 128, 128, 128), Color::WHITE * 0.5);
+        // saturating, not wrapping
+        assert_eq!(Color::rgba(255, 200, 30, 255),
+                   Color::rgba(200, 100, 10, 200) + Color::rgba(100, 100, 20, 100));
+        assert_eq!(Color::rgba(0, 50, 0, 0),
+                   Color::rgba(20, 100, 10, 100) - Color::rgba(100, 50, 20, 200));
+        assert_eq!(Color::WHITE, Color::rgba(100, 200, 128, 255) * 3.0);
+        assert_eq!(Color::TRANSPARENT, Color::WHITE * -1.0);
+        assert_eq!(Color::rgba(1, 2, 3, 4), Color::rgba(1, 2, 3, 4) * 1.0);
+        // on the real channels
+        assert_eq!(255, (Color::RED + Color::GREEN).red());
+        assert_eq!(0, (Color::WHITE - Color::RED).red());
+        assert_eq!(255, (Color::WHITE - Color::RED).blue());
+        assert_eq!(20, (Color::rgba(10, 0, 0, 255) * 2.0).red());
+    }
+
+    #[test]
+    fn parse_x11() {
+        assert_eq!(Color::parse("#ff0000"), Color::parse("rgb:ff/00/00"));
+        assert_eq!(Color::parse("#ff0000"), Color::parse("rgb:f/0/0"));
+        assert_eq!(Color::parse("#ff0000"), Color::parse(" rgb:FFFF/0000/000 "));
+        assert_eq!(Some(Color::rgba(0x11, 0x22, 0xaa, 255)), Color::parse("rgb:1/2/a"));
+        assert_eq!(Some(Color::rgba(0x12, 0x34, 0x56, 255)), Color::parse("rgb:12/34/56"));
+        assert_eq!(Some(Color::rgba(128, 128, 127, 255)), Color::parse("rgb:8000/800/7f"));
+        assert_eq!(Some(Color::rgba(255, 0, 128, 255)), Color::parse("rgb:fff/0/8080"));
+        for &s in &["rgb:ff/00", "rgb:ff/00/00/00", "rgb:ff//00", "rgb:/ff/00/00",
+                    "rgb:ff/00/00/", "rgb:fffff/0/0", "rgb:gg/00/00", "rgb:ff,00,00",
+                    "rgb:ff/+0/00", "rgb: ff/00/00", "rgb:", "rgb:é/0/0"] {
+            assert_eq!(None, Color::parse(s), "{}", s);
+            assert_eq!(Err(ColorParseError::InvalidFunction), Color::try_parse(s), "{}", s);
+        }
+    }
+
+    #[test]
+    fn gradient_steps() {
+        let (from, to) = (Color::rgba(10, 20, 30, 40), Color::rgba(250, 240, 230, 220));
+        assert_eq!(Vec::<Color>::new(), from.gradient_steps(&to, 0));
+        assert_eq!(vec![from], from.gradient_steps(&to, 1));
+        assert_eq!(vec![from, to], from.gradient_steps(&to, 2));
+        for n in 2..50 {
+            let steps = from.gradient_steps(&to, n);
+            assert_eq!(n, steps.len());
+            assert_eq!(Some(&from), steps.first());
+            assert_eq!(Some(&to), steps.last());
+            assert!(steps.windows(2).all(|pair| pair[0].red() <= pair[1].red()), "{:?}", steps);
+        }
+        assert_eq!(vec![Color::BLACK, Color::rgba(128, 128, 128, 255), Color::WHITE],
+                   Color::BLACK.gradient_steps(&Color::WHITE, 3));
+        assert_eq!(vec![Color::RED, Color::rgba(191, 0, 64, 255), Color::rgba(128, 0, 128, 255),
+                        Color::rgba(64, 0, 191, 255), Color::BLUE],
+                   Color::RED.gradient_steps(&Color::BLUE, 5));
+    }
+
+    #[test]
+    fn simplest_hex() {
+        assert_eq!("#f00", Color::rgba(255, 0, 0, 255).simplest_hex());
+        assert_eq!("#18c", Color::rgba(0x11, 0x88, 0xcc, 255).simplest_hex());
+        assert_eq!("#ff0001", Color::rgba(255, 0, 1, 255).simplest_hex());
+        assert_eq!("#123456", Color::rgba(0x12, 0x34, 0x56, 255).simplest_hex());
+        assert_eq!("#f008", Color::rgba(255, 0, 0, 0x88).simplest_hex());
+        assert_eq!("#0000", Color::TRANSPARENT.simplest_hex());
+        assert_eq!("#80ff0000", Color::rgba(255, 0, 0, 0x80).simplest_hex());
+        assert_eq!("#2341", Color::rgba(0x22, 0x33, 0x44, 0x11).simplest_hex());
+        assert_eq!("#12223344", Color::rgba(0x22, 0x33, 0x44, 0x12).simplest_hex());
+        // parse reads back every color
+        for &color in &[Color::RED, Color::rgba(255, 0, 0, 0x88), Color::rgba(1, 2, 3, 4),
+                        Color::rgba(0xaa, 0xbb, 0xcc, 0xdd), Color::rgba(0xaa, 0xbb, 0xcc, 0xde)] {
+            assert_eq!(Some(color), Color::parse(&color.simplest_hex()), "{:?}", color);
+        }
+    }
+
+    #[test]
+    fn parse_multibyte() {
+        // "é" is 2 bytes, "€" is 3 and "😀" is 4, so the byte lengths of
+        // these are the lengths of the hex formats, but their char counts aren't
+        let inputs = ["#é0000", "#é00000", "#0é00000", "é0000", "é", "#é", "0xé€",
+                      "€", "#€00", "#f€", "😀", "#😀", "#😀00", "#😀0000", "0x😀😀",
+                      "#😀😀😀😀", "#€€€€", "#ééé", "#éééé", "#ffffffé", "#fffffffé",
+                      "rgb(é, 0, 0)", "rgb(255 0 0 / é)", "rgb(😀)", "é(1, 2, 3)",
+                      "hsl(1€ 2% 3%)", "hwb(0 é é)", "rgb:é/€/😀", "rgb:ff/ff/fé",
+                      "#f\u{301}ff"];
+        for &s in &inputs {
+            assert_eq!(None, Color::parse(s), "{}", s);
+            assert_eq!(None, Color::parse_cached(s), "{}", s);
+            assert!(Color::try_parse(s).is_err(), "{}", s);
+            assert!(Color::parse_many(s).is_err(), "{}", s);
+            assert!(Color::parse_css_color4(s).is_err(), "{}", s);
+            assert_eq!(None, Color::parse_with(s, ParseMode::Strict), "{}", s);
+            assert_eq!(None, Color::parse_with(s, ParseMode::Lenient), "{}", s);
+            assert_eq!(None, Color::parse_with_order(s, HexOrder::Rgba), "{}", s);
+        }
+        // only ASCII whitespace is trimmed
+        assert_eq!(None, Color::parse("\u{a0}#fff"));
+        assert_eq!(None, Color::parse("#fff\u{a0}"));
+        // the positions of invalid digits are in chars, not bytes
+        assert_eq!(Err(ColorParseError::InvalidDigit { position: 2, found: 'é' }),
+                   Color::try_parse("#fé0000"));
+        assert_eq!(Err(ColorParseError::InvalidDigit { position: 3, found: '😀' }),
+                   Color::parse_css_color4("#ff😀000"));
+    }
+
+    #[test]
+    fn to_css_string() {
+        assert_eq!("rgb(255, 0, 0)", Color::RED.to_css_string());
+        assert_eq!("rgb(18, 52, 86)", Color::rgba(0x12, 0x34, 0x56, 255).to_css_string());
+        assert_eq!("rgba(255, 0, 0, 0.502)", Color::rgba(255, 0, 0, 128).to_css_string());
+        assert_eq!("rgba(1, 2, 3, 0.2)", Color::rgba(1, 2, 3, 51).to_css_string());
+        assert_eq!("rgba(0, 0, 0, 0)", Color::TRANSPARENT.to_css_string());
+        for alpha in 0..=255 {
+            let color = Color::rgba(10, 200, 30, alpha);
+            assert_eq!(Some(color), Color::parse(&color.to_css_string()));
+            assert_eq!(Ok(color), Color::parse_css_color4(&color.to_css_string()));
+        }
+    }
+
+    #[test]
+    fn closest_named() {
+        assert_eq!("red", Color::rgba(250, 5, 5, 255).closest_named());
+        assert_eq!("red", Color::rgba(255, 0, 0, 255).closest_named());
+        assert_eq!("gray", Color::rgba(128, 128, 128, 255).closest_named());
+        assert_eq!("gray", Color::rgba(130, 127, 129, 255).closest_named());
+        assert_eq!("black", Color::TRANSPARENT.closest_named());
+        assert_eq!("aqua", Color::rgba(0, 250, 255, 100).closest_named());
+        assert_eq!("cornflowerblue", Color::rgba(100, 150, 240, 255).closest_named());
+        // named colors are their own closest name
+        for &(name, rgb) in NAMED_COLORS {
+            assert_eq!(Color::from(rgb).name(), Some(Color::from(rgb).closest_named()), "{}", name);
+        }
+    }
+
+    #[test]
+    fn parse_channel_list() {
+        assert_eq!(Color::parse("#ff0000"), Color::parse("255,0,0"));
+        assert_eq!(Some(Color::rgba(255, 0, 0, 128)), Color::parse("255,0,0,128"));
+        assert_eq!(Some(Color::rgba(1, 2, 3, 255)), Color::parse(" 1, 2 ,3 "));
+        assert_eq!(Some(Color::TRANSPARENT), Color::parse("0,0,0,0"));
+        for &s in &["256,0,0", "255,0", "255,0,0,0,0", "255,-1,0", "255,,0", "255,0,0,",
+                    "ff,0,0", "255,0,0,0.5", "1.0,0,0", ",", "255 0 0"] {
+            assert_eq!(None, Color::parse(s), "{}", s);
+        }
+        assert_eq!(Err(ColorParseError::InvalidFunction), Color::try_parse("256,0,0"));
+    }
+
+    #[test]
+    fn fill_row() {
+        for &color in &[Color::rgba(255, 0, 0, 255), Color::rgba(12, 200, 99, 128),
+                        Color::rgba(255, 255, 255, 0), Color::rgba(1, 2, 3, 4)] {
+            let packed = color.mul_alpha_into_u32();
+            assert_eq!(color.as_cairo_argb32(), packed.get());
+            let mut row = [0xDEADBEEF; 7];
+            packed.fill_row(&mut row);
+            assert_eq!([color.as_cairo_argb32(); 7], row);
+        }
+        let mut empty: [u32; 0] = [];
+        Color::RED.mul_alpha_into_u32().fill_row(&mut empty);
+    }
+
+    #[test]
+    fn parse_optional() {
+        assert_eq!(Ok(None), Color::parse_optional("none"));
+        assert_eq!(Ok(None), Color::parse_optional(" NONE\t"));
+        assert_eq!(Ok(None), Color::parse_optional("none;"));
+        assert_eq!(Ok(Some(Color::TRANSPARENT)), Color::parse_optional("transparent"));
+        assert_eq!(Ok(Some(Color::rgba(0, 0, 0, 0))), Color::parse_optional("Transparent"));
+        assert_eq!(Ok(Some(Color::RED)), Color::parse_optional("#f00"));
+        assert_eq!(Err(ColorParseError::EmptyInput), Color::parse_optional(""));
+        assert!(Color::parse_optional("nonee").is_err());
+        // "none" is no color
+        assert_eq!(None, Color::parse("none"));
+    }
+
+    #[test]
+    fn apply_opacity_rule() {
+        let color = Color::rgba(10, 20, 30, 255);
+        assert_eq!(Color::rgba(10, 20, 30, 204), color.apply_opacity_rule(0.8));
+        assert!(color.apply_opacity_rule(0.0).is_transparent());
+        assert_eq!(color, color.apply_opacity_rule(1.0));
+        assert_eq!(color, color.apply_opacity_rule(1.5));
+        assert_eq!(Color::rgba(10, 20, 30, 0), color.apply_opacity_rule(-1.0));
+        assert_eq!(Color::rgba(10, 20, 30, 64), color.with_alpha(128).apply_opacity_rule(0.5));
+    }
+
+    #[test]
+    fn channels() {
+        assert_eq!([255, 0, 0, 255], Color::RED.channels());
+        assert_eq!([1, 2, 3, 4], Color::rgba(1, 2, 3, 4).channels());
+        assert_eq!(Color::rgba(1, 2, 3, 4), Color::from_channels([1, 2, 3, 4]));
+        for &color in &[Color::RED, Color::BLUE, Color::TRANSPARENT, Color::WHITE,
+                        Color::rgba(12, 34, 56, 78), Color::rgba(200, 100, 50, 128)] {
+            assert_eq!(color, Color::from_channels(color.channels()));
+        }
+        let inverted = Color::from_channels(Color::rgba(0, 55, 255, 0).channels().map(|c| 255 - c));
+        assert_eq!(Color::rgba(255, 200, 0, 255), inverted);
+    }
+
+    #[test]
+    fn brightness_adjust() {
+        let color = Color::rgba(10, 100, 250, 128);
+        assert_eq!(color, color.brightness_adjust(0));
+        assert_eq!(Color::rgba(30, 120, 255, 128), color.brightness_adjust(20));
+        assert_eq!(Color::rgba(0, 80, 230, 128), color.brightness_adjust(-20));
+        assert_eq!(Color::rgba(255, 255, 255, 128), color.brightness_adjust(300));
+        assert_eq!(Color::rgba(0, 0, 0, 128), color.brightness_adjust(-300));
+        assert_eq!(Color::WHITE, Color::BLACK.brightness_adjust(i16::MAX));
+        assert_eq!(Color::BLACK, Color::WHITE.brightness_adjust(i16::MIN));
+    }
+
+    #[test]
+    fn premultiply_keep_hue() {
+        let transparent_red = Color::rgba(255, 0, 0, 0);
+        assert_eq!(Color::TRANSPARENT, transparent_red.premultiply());
+        assert_eq!(transparent_red, transparent_red.premultiply_keep_hue());
+        assert_eq!(255, transparent_red.premultiply_keep_hue().red());
+        assert!(!transparent_red.premultiply_keep_hue().is_valid_premultiplied());
+        // the same as premultiply if there is any alpha
+        for &color in &[Color::rgba(255, 0, 0, 1), Color::rgba(200, 100, 50, 128), Color::WHITE] {
+            assert_eq!(color.premultiply(), color.premultiply_keep_hue());
+        }
+    }
+
+    #[test]
+    fn parse_semicolon() {
+        assert_eq!(Some(Color::RED), Color::parse("#ff0000;"));
+        assert_eq!(Some(Color::RED), Color::parse(" #ff0000 ; "));
+        assert_eq!(Some(Color::RED), Color::parse("red;"));
+        assert_eq!(Some(Color::RED), Color::parse("rgb(255, 0, 0);"));
+        assert_eq!(None, Color::parse("#ff0000;x"));
+        assert_eq!(None, Color::parse("#ff0000;;"));
+        assert_eq!(None, Color::parse(";#ff0000"));
+        assert_eq!(None, Color::parse(";"));
+        assert_eq!(Err(ColorParseError::EmptyInput), Color::try_parse(" ; "));
+        assert_eq!(Err(ColorParseError::InvalidDigit { position: 3, found: ';' }),
+                   Color::try_parse("#ff;000;"));
+        // the strict mode only accepts the canonical formats
+        assert_eq!(None, Color::parse_with("#ff0000;", ParseMode::Strict));
+    }
+
+}
+
+/// The same tests as above, but with red and blue not switched.
+#[cfg(all(test, not(feature = "wlc-color-swap")))]
+mod test_no_swap {
+
+    use ::render::Color;
+
+    #[test]
+    fn test_from_u32() {
+        let r: Color = 0xFF0000.into();
+        let g: Color = 0x00FF00.into();
+        let b: Color = 0x0000FF.into();
+        assert_eq!((0xFF, 0x00, 0x00, 0xFF), r.values());
+        assert_eq!((0x00, 0xFF, 0x00, 0xFF), g.values());
+        assert_eq!((0x00, 0x00, 0xFF, 0xFF), b.values());
+    }
+
+    #[test]
+    fn parse_rgb() {
+        assert_eq!((0,   0,   0,   255), Color::parse_rgb("000000").unwrap().values());
+        assert_eq!((255, 0,   0,   255), Color::parse_rgb("ff0000").unwrap().values());
+        assert_eq!((0,   255, 0,   255), Color::parse_rgb("00ff00").unwrap().values());
+        assert_eq!((0,   0,   255, 255), Color::parse_rgb("0000ff").unwrap().values());
+        assert_eq!((255, 255, 255, 255), Color::parse_rgb("ffffff").unwrap().values());
+    }
+
     #[test]
     fn parse_argb() {
-        // test some valid color values
-        let rgb_transparent = Color::parse_argb("00000000").unwrap();
-        assert_eq!(0,   rgb_transparent.red);
-        assert_eq!(0,   rgb_transparent.green);
-        assert_eq!(0,   rgb_transparent.blue);
-        assert_eq!(0,   rgb_transparent.alpha);
-        let rgb_red   = Color::parse_argb("40ff0000").unwrap();
-        assert_eq!(0,   rgb_red.red);
-        assert_eq!(0,   rgb_red.green);
-        assert_eq!(255, rgb_red.blue);
-        assert_eq!(64,  rgb_red.alpha);
-        let rgb_green = Color::parse_argb("8000ff00").unwrap();
-        assert_eq!(0,   rgb_green.red);
-        assert_eq!(255, rgb_green.green);
-        assert_eq!(0,   rgb_green.blue);
-        assert_eq!(128, rgb_green.alpha);
-        let rgb_blue  = Color::parse_argb("c00000ff").unwrap();
-        assert_eq!(255, rgb_blue.red);
-        assert_eq!(0,   rgb_blue.green);
-        assert_eq!(0,   rgb_blue.blue);
-        assert_eq!(192, rgb_blue.alpha);
-        let rgb_white = Color::parse_argb("ffffffff").unwrap();
-        assert_eq!(255, rgb_white.red);
-        assert_eq!(255, rgb_white.green);
-        assert_eq!(255, rgb_white.blue);
-        assert_eq!(255, rgb_white.alpha);
-        // test some invalid formats
-        assert_eq!(false, Color::parse_argb("").is_some());
-        assert_eq!(false, Color::parse_argb("0").is_some());
-        assert_eq!(false, Color::parse_argb("00").is_some());
-        assert_eq!(false, Color::parse_argb("000").is_some());
-        assert_eq!(false, Color::parse_argb("0000").is_some());
-        assert_eq!(false, Color::parse_argb("00000").is_some());
-        assert_eq!(false, Color::parse_argb("000000").is_some());
-        assert_eq!(false, Color::parse_argb("0000000").is_some());
-        assert_eq!(false, Color::parse_argb("xxxxxxxx").is_some());
-        assert_eq!(false, Color::parse_argb("000000000").is_some());
-        assert_eq!(false, Color::parse_argb("0000000000").is_some());
+        assert_eq!((0,   0,   0,   0),   Color::parse_argb("00000000").unwrap().values());
+        assert_eq!((255, 0,   0,   64),  Color::parse_argb("40ff0000").unwrap().values());
+        assert_eq!((0,   255, 0,   128), Color::parse_argb("8000ff00").unwrap().values());
+        assert_eq!((0,   0,   255, 192), Color::parse_argb("c00000ff").unwrap().values());
+        assert_eq!((255, 255, 255, 255), Color::parse_argb("ffffffff").unwrap().values());
     }
 
     #[test]
     fn parse() {
-        // #-prefixed (HTML-style)
-        assert_eq!(true, Color::parse("#000000").is_some());
-        assert_eq!(true, Color::parse("#00000000").is_some());
-        // 0x-prefixed (Hex-style)
-        assert_eq!(true, Color::parse("0x000000").is_some());
-        assert_eq!(true, Color::parse("0x00000000").is_some());
-        // No prefix
-        assert_eq!(true, Color::parse("000000").is_some());
-        assert_eq!(true, Color::parse("00000000").is_some());
-        // Actual colors
-        let red = Color::parse("0xFFFF0000").unwrap();
-        assert_eq!(0,   red.red);
-        assert_eq!(0,   red.green);
-        assert_eq!(255, red.blue);
-        assert_eq!(255, red.alpha);
-        let green = Color::parse("0xFF00FF00").unwrap();
-        assert_eq!(0,   green.red);
-        assert_eq!(255, green.green);
-        assert_eq!(0,   green.blue);
-        assert_eq!(255, green.alpha);
-        let blue = Color::parse("0xFF0000FF").unwrap();
-        assert_eq!(255, blue.red);
-        assert_eq!(0,   blue.green);
-        assert_eq!(0,   blue.blue);
-        assert_eq!(255, blue.alpha);
-        // wrong formats
-        assert_eq!(false, Color::parse("").is_some());
-        assert_eq!(false, Color::parse("0").is_some());
-        assert_eq!(false, Color::parse("00").is_some());
-        assert_eq!(false, Color::parse("000").is_some());
-        assert_eq!(false, Color::parse("0000").is_some());
-        assert_eq!(false, Color::parse("00000").is_some());
-        assert_eq!(false, Color::parse("0000000").is_some());
+        assert_eq!((255, 0,   0,   255), Color::parse("0xFFFF0000").unwrap().values());
+        assert_eq!((0,   255, 0,   255), Color::parse("0xFF00FF00").unwrap().values());
+        assert_eq!((0,   0,   255, 255), Color::parse("0xFF0000FF").unwrap().values());
+        assert_eq!((255, 0,   0,   255), Color::parse("rgb(255, 0, 0)").unwrap().values());
+        assert_eq!((255, 0,   0,   255), Color::parse("red").unwrap().values());
     }
 
 }
-
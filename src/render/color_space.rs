@@ -0,0 +1,141 @@
+//! Colors tagged with the space their channels are in, so that sRGB encoded
+//! and linear light colors can't be mixed by accident.
+#![allow(dead_code)] // Nothing mixes colors in linear light with it yet
+
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+use super::color::Color;
+
+/// A space the channels of a `TaggedColor` can be in.
+pub trait ColorSpace: Clone + Copy + Debug + PartialEq {}
+
+/// The channels are sRGB encoded, like the ones of a `Color`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct Srgb;
+
+/// The channels are in linear light, where mixing colors is accurate.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct Linear;
+
+impl ColorSpace for Srgb {}
+impl ColorSpace for Linear {}
+
+/// A color whose channels (from 0.0 to 1.0) are in the color space `S`.
+///
+/// The space is only tracked in the type, so a `TaggedColor` is no bigger
+/// than its channels. Only colors in the same space can be mixed, and a
+/// `Color` can only be made from a `TaggedColor<Srgb>`.
+///
+/// The alpha channel is never gamma encoded, so it is the same in both spaces.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TaggedColor<S: ColorSpace> {
+    red: f32,
+    green: f32,
+    blue: f32,
+    alpha: f32,
+    space: PhantomData<S>
+}
+
+impl<S: ColorSpace> TaggedColor<S> {
+    fn new(red: f32, green: f32, blue: f32, alpha: f32) -> Self {
+        TaggedColor { red, green, blue, alpha, space: PhantomData }
+    }
+
+    /// Gets the red, green, blue and alpha channels, from 0.0 to 1.0.
+    pub fn channels(&self) -> (f32, f32, f32, f32) {
+        (self.red, self.green, self.blue, self.alpha)
+    }
+
+    /// Linearly interpolates between this color (at `t` = 0.0) and
+    /// `other` (at `t` = 1.0), in the space both colors are in.
+    ///
+    /// `t` is clamped to 0.0-1.0.
+    pub fn interpolate(&self, other: &Self, t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        let mix = |from: f32, to: f32| from + (to - from) * t;
+        TaggedColor::new(mix(self.red, other.red),
+                         mix(self.green, other.green),
+                         mix(self.blue, other.blue),
+                         mix(self.alpha, other.alpha))
+    }
+}
+
+impl TaggedColor<Srgb> {
+    /// Converts the channels into linear light.
+    pub fn to_linear(self) -> TaggedColor<Linear> {
+        TaggedColor::new(Color::srgb_to_linear(self.red),
+                         Color::srgb_to_linear(self.green),
+                         Color::srgb_to_linear(self.blue),
+                         self.alpha)
+    }
+
+    /// Converts the color back into a `Color`, rounding the channels.
+    pub fn to_color(self) -> Color {
+        Color::clamp_to_gamut(self.red * 255.0,
+                              self.green * 255.0,
+                              self.blue * 255.0,
+                              self.alpha * 255.0)
+    }
+}
+
+impl TaggedColor<Linear> {
+    /// Converts the channels from linear light back into sRGB.
+    pub fn to_srgb(self) -> TaggedColor<Srgb> {
+        TaggedColor::new(Color::linear_to_srgb(self.red),
+                         Color::linear_to_srgb(self.green),
+                         Color::linear_to_srgb(self.blue),
+                         self.alpha)
+    }
+}
+
+impl From<Color> for TaggedColor<Srgb> {
+    fn from(color: Color) -> Self {
+        TaggedColor::new(color.red_f32(), color.green_f32(), color.blue_f32(), color.alpha_f32())
+    }
+}
+
+impl From<TaggedColor<Srgb>> for Color {
+    fn from(color: TaggedColor<Srgb>) -> Self {
+        color.to_color()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::mem::size_of;
+    use super::*;
+
+    #[test]
+    fn zero_cost() {
+        assert_eq!(size_of::<[f32; 4]>(), size_of::<TaggedColor<Srgb>>());
+        assert_eq!(size_of::<[f32; 4]>(), size_of::<TaggedColor<Linear>>());
+    }
+
+    #[test]
+    fn blends_differ() {
+        let black = TaggedColor::from(Color::BLACK);
+        let white = TaggedColor::from(Color::WHITE);
+        let srgb = black.interpolate(&white, 0.5).to_color();
+        let linear = black.to_linear().interpolate(&white.to_linear(), 0.5).to_srgb().to_color();
+        assert_eq!(Color::rgba(128, 128, 128, 255), srgb);
+        assert_eq!(Color::rgba(188, 188, 188, 255), linear);
+        // the same as the blending methods of Color
+        assert_eq!(Color::BLACK.interpolate(&Color::WHITE, 0.5), srgb);
+    }
+
+    #[test]
+    fn round_trip() {
+        for &channel in &[0, 1, 5, 10, 11, 12, 100, 128, 254, 255] {
+            let color = Color::rgba(channel, 255 - channel, channel / 2, channel);
+            let tagged = TaggedColor::from(color);
+            assert_eq!(color, tagged.to_linear().to_srgb().to_color());
+            assert_eq!(color, Color::from(tagged));
+        }
+        let (red, green, blue, alpha) = TaggedColor::from(Color::rgba(255, 128, 0, 51))
+            .to_linear()
+            .channels();
+        assert_eq!((1.0, 0.0, 0.2), (red, blue, alpha));
+        assert!((green - 0.2158).abs() < 0.001, "{}", green);
+    }
+}
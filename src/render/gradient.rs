@@ -0,0 +1,267 @@
+//! Linear gradients between colors, eg for gradient borders.
+#![allow(dead_code)] // No border is drawn with a gradient yet
+
+use super::color::Color;
+
+/// The ways creating a `Gradient` can go wrong.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GradientError {
+    /// A gradient needs at least one stop.
+    NoStops,
+    /// The position of a stop was not in the range 0.0-1.0.
+    StopOutOfRange(f32),
+    /// The string to parse was not a "linear-gradient(...)".
+    NotLinearGradient,
+    /// A parsed gradient needs at least two stops, it had this many.
+    TooFewStops(usize),
+    /// The stop at this index (from 0) doesn't start with a valid color,
+    /// or has an invalid position.
+    InvalidStop(usize)
+}
+
+/// A linear gradient, defined by colors at positions from 0.0 to 1.0.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gradient {
+    /// The positions and colors of the stops, sorted by position.
+    stops: Vec<(f32, Color)>
+}
+
+impl Gradient {
+    /// Creates a gradient from stops at positions from 0.0 to 1.0.
+    ///
+    /// The stops don't need to be sorted. Stops at the same position are
+    /// kept in the given order, which makes a hard edge between the colors.
+    pub fn new(mut stops: Vec<(f32, Color)>) -> Result<Self, GradientError> {
+        if stops.is_empty() {
+            return Err(GradientError::NoStops)
+        }
+        if let Some(&(position, _)) = stops.iter()
+            .find(|&&(position, _)| !(0.0..=1.0).contains(&position)) {
+            return Err(GradientError::StopOutOfRange(position))
+        }
+        // No NaNs are left, so the positions can be compared
+        stops.sort_by(|&(a, _), &(b, _)| a.partial_cmp(&b).unwrap());
+        Ok(Gradient { stops })
+    }
+
+    /// Parses a CSS style gradient, eg "linear-gradient(#f00, #00f)".
+    ///
+    /// Every stop is a color that `Color::parse` accepts, optionally followed
+    /// by a position as a percentage, eg "linear-gradient(red, blue 80%)".
+    /// Like in CSS, the first stop is at 0% and the last at 100% if they
+    /// have no position, and the stops without a position in between are
+    /// spread evenly between the stops around them. Directions and angles
+    /// are not supported, the function name is case-insensitive.
+    pub fn parse(s: &str) -> Result<Self, GradientError> {
+        let s = s.trim_matches(|c: char| c.is_ascii_whitespace());
+        let open = s.find('(').ok_or(GradientError::NotLinearGradient)?;
+        let args = s[open + 1..].strip_suffix(')')
+            .filter(|_| s[..open].trim_end().eq_ignore_ascii_case("linear-gradient"))
+            .ok_or(GradientError::NotLinearGradient)?;
+        let args = split_stops(args);
+        if args.len() < 2 {
+            return Err(GradientError::TooFewStops(args.len()))
+        }
+        let mut positions = Vec::with_capacity(args.len());
+        let mut colors = Vec::with_capacity(args.len());
+        for (index, arg) in args.into_iter().enumerate() {
+            let (color, position) = parse_stop(arg).ok_or(GradientError::InvalidStop(index))?;
+            colors.push(color);
+            positions.push(position);
+        }
+        Gradient::new(spread_positions(positions).into_iter().zip(colors).collect())
+    }
+
+    /// Gets the stops of the gradient, sorted by position.
+    pub fn stops(&self) -> &[(f32, Color)] {
+        &self.stops
+    }
+
+    /// Gets the color of the gradient at `t`, which is clamped to 0.0-1.0.
+    ///
+    /// Before the first stop and after the last stop, their colors are used.
+    pub fn sample(&self, t: f32) -> Color {
+        let t = t.clamp(0.0, 1.0);
+        let (first_position, first_color) = self.stops[0];
+        if t <= first_position {
+            return first_color
+        }
+        for pair in self.stops.windows(2) {
+            let (from_position, from_color) = pair[0];
+            let (to_position, to_color) = pair[1];
+            if t <= to_position {
+                let local_t = (t - from_position) / (to_position - from_position);
+                return from_color.interpolate(&to_color, local_t)
+            }
+        }
+        self.stops[self.stops.len() - 1].1
+    }
+}
+
+/// Splits the arguments of a gradient at the commas that aren't part of a
+/// color (eg of "rgb(0, 0, 0)"). An empty list has no arguments.
+fn split_stops(args: &str) -> Vec<&str> {
+    if args.trim().is_empty() {
+        return Vec::new()
+    }
+    let mut stops = Vec::new();
+    let mut depth = 0_usize;
+    let mut start = 0;
+    for (index, c) in args.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                stops.push(&args[start..index]);
+                start = index + 1;
+            },
+            _ => {}
+        }
+    }
+    stops.push(&args[start..]);
+    stops
+}
+
+/// Parses a stop of a gradient: a color, optionally followed by a percentage.
+fn parse_stop(stop: &str) -> Option<(Color, Option<f32>)> {
+    let stop = stop.trim();
+    if let Some((color, position)) = stop.rsplit_once(|c: char| c.is_ascii_whitespace()) {
+        if let Some(percent) = position.strip_suffix('%') {
+            let percent: f32 = percent.parse().ok().filter(|percent: &f32| percent.is_finite())?;
+            return Color::parse(color).map(|color| (color, Some(percent / 100.0)))
+        }
+    }
+    Color::parse(stop).map(|color| (color, None))
+}
+
+/// Fills in the missing positions of stops, which are spread evenly between
+/// the stops around them. The first and last stop default to 0.0 and 1.0.
+fn spread_positions(positions: Vec<Option<f32>>) -> Vec<f32> {
+    let last = positions.len() - 1;
+    let mut spread = Vec::with_capacity(positions.len());
+    let mut previous = (0, positions[0].unwrap_or(0.0));
+    for (index, position) in positions.iter().enumerate() {
+        let position = match *position {
+            Some(position) => position,
+            None if index == 0 => 0.0,
+            None if index == last => 1.0,
+            None => continue
+        };
+        // the stops since the previous one with a position are in between
+        let (from_index, from) = previous;
+        for between in from_index + 1..index {
+            let t = (between - from_index) as f32 / (index - from_index) as f32;
+            spread.push(from + (position - from) * t);
+        }
+        spread.push(position);
+        previous = (index, position);
+    }
+    spread
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn new() {
+        let black = Color::rgba(0, 0, 0, 255);
+        let white = Color::rgba(255, 255, 255, 255);
+        assert_eq!(Err(GradientError::NoStops), Gradient::new(vec![]));
+        assert_eq!(Err(GradientError::StopOutOfRange(1.5)),
+                   Gradient::new(vec![(0.0, black), (1.5, white)]));
+        assert_eq!(Err(GradientError::StopOutOfRange(-0.5)),
+                   Gradient::new(vec![(-0.5, black), (1.0, white)]));
+        assert!(Gradient::new(vec![(f32::NAN, black)]).is_err());
+        // the stops are sorted
+        let gradient = Gradient::new(vec![(1.0, white), (0.0, black)]).unwrap();
+        assert_eq!(&[(0.0, black), (1.0, white)], gradient.stops());
+    }
+
+    #[test]
+    fn sample_two_stops() {
+        let black = Color::rgba(0, 0, 0, 255);
+        let white = Color::rgba(255, 255, 255, 255);
+        let gradient = Gradient::new(vec![(0.0, black), (1.0, white)]).unwrap();
+        assert_eq!(black, gradient.sample(0.0));
+        assert_eq!(Color::rgba(64, 64, 64, 255), gradient.sample(0.25));
+        assert_eq!(Color::rgba(128, 128, 128, 255), gradient.sample(0.5));
+        assert_eq!(Color::rgba(191, 191, 191, 255), gradient.sample(0.75));
+        assert_eq!(white, gradient.sample(1.0));
+        // t is clamped
+        assert_eq!(black, gradient.sample(-1.0));
+        assert_eq!(white, gradient.sample(2.0));
+    }
+
+    #[test]
+    fn sample_three_stops() {
+        let red = Color::rgba(255, 0, 0, 255);
+        let green = Color::rgba(0, 255, 0, 255);
+        let blue = Color::rgba(0, 0, 255, 255);
+        let gradient = Gradient::new(vec![(0.0, red), (0.5, green), (1.0, blue)])
+            .unwrap();
+        assert_eq!(red, gradient.sample(0.0));
+        assert_eq!(Color::rgba(128, 128, 0, 255), gradient.sample(0.25));
+        assert_eq!(green, gradient.sample(0.5));
+        assert_eq!(Color::rgba(0, 128, 128, 255), gradient.sample(0.75));
+        assert_eq!(blue, gradient.sample(1.0));
+    }
+
+    #[test]
+    fn sample_partial_stops() {
+        let red = Color::rgba(255, 0, 0, 255);
+        let blue = Color::rgba(0, 0, 255, 255);
+        // single stops are a solid color
+        let gradient = Gradient::new(vec![(0.3, red)]).unwrap();
+        assert_eq!(red, gradient.sample(0.0));
+        assert_eq!(red, gradient.sample(1.0));
+        // the first and last colors are extended to the edges
+        let gradient = Gradient::new(vec![(0.25, red), (0.75, blue)]).unwrap();
+        assert_eq!(red, gradient.sample(0.1));
+        assert_eq!(Color::rgba(128, 0, 128, 255), gradient.sample(0.5));
+        assert_eq!(blue, gradient.sample(0.9));
+        // stops at the same position make a hard edge
+        let gradient = Gradient::new(vec![(0.0, red), (0.5, red), (0.5, blue), (1.0, blue)])
+            .unwrap();
+        assert_eq!(red, gradient.sample(0.49));
+        assert_eq!(blue, gradient.sample(0.51));
+    }
+
+    #[test]
+    fn parse() {
+        let red = Color::rgba(255, 0, 0, 255);
+        let green = Color::rgba(0, 128, 0, 255);
+        let blue = Color::rgba(0, 0, 255, 255);
+        let gradient = Gradient::parse("linear-gradient(#f00, #00f)").unwrap();
+        assert_eq!(&[(0.0, red), (1.0, blue)], gradient.stops());
+        let gradient = Gradient::parse(" Linear-Gradient(red,green , blue) ").unwrap();
+        assert_eq!(&[(0.0, red), (0.5, green), (1.0, blue)], gradient.stops());
+        // colors with commas and explicit positions
+        let gradient = Gradient::parse("linear-gradient(rgb(255, 0, 0), green, \
+                                        hsl(240, 100%, 50%) 50%, red 75%, blue)").unwrap();
+        assert_eq!(&[(0.0, red), (0.25, green), (0.5, blue), (0.75, red), (1.0, blue)],
+                   gradient.stops());
+        let gradient = Gradient::parse("linear-gradient(red 50%, green, blue)").unwrap();
+        assert_eq!(&[(0.5, red), (0.75, green), (1.0, blue)], gradient.stops());
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert_eq!(Err(GradientError::NotLinearGradient), Gradient::parse("#f00, #00f"));
+        assert_eq!(Err(GradientError::NotLinearGradient),
+                   Gradient::parse("radial-gradient(#f00, #00f)"));
+        assert_eq!(Err(GradientError::NotLinearGradient),
+                   Gradient::parse("linear-gradient(#f00, #00f"));
+        assert_eq!(Err(GradientError::TooFewStops(0)), Gradient::parse("linear-gradient()"));
+        assert_eq!(Err(GradientError::TooFewStops(1)),
+                   Gradient::parse("linear-gradient(#f00)"));
+        assert_eq!(Err(GradientError::InvalidStop(1)),
+                   Gradient::parse("linear-gradient(#f00, bluish)"));
+        assert_eq!(Err(GradientError::InvalidStop(0)),
+                   Gradient::parse("linear-gradient(#f00 half, #00f)"));
+        assert_eq!(Err(GradientError::InvalidStop(1)),
+                   Gradient::parse("linear-gradient(#f00, , #00f)"));
+        assert_eq!(Err(GradientError::StopOutOfRange(1.5)),
+                   Gradient::parse("linear-gradient(#f00, #00f 150%)"));
+    }
+}
@@ -0,0 +1,108 @@
+//! Flattening colors stacked on top of each other into one, eg for
+//! decorations made of several translucent parts.
+#![allow(dead_code)] // The decorations are still drawn with a single color
+
+use std::iter::FromIterator;
+
+use cairo::BorrowError;
+
+use super::buffer::Buffer;
+use super::color::Color;
+
+/// Colored layers, from the bottom to the top.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct LayerStack {
+    layers: Vec<Color>
+}
+
+impl LayerStack {
+    /// Creates a stack without any layers.
+    pub fn new() -> Self {
+        LayerStack::default()
+    }
+
+    /// Adds a layer on top of the others.
+    pub fn push(&mut self, color: Color) {
+        self.layers.push(color);
+    }
+
+    /// Removes the top layer.
+    pub fn pop(&mut self) -> Option<Color> {
+        self.layers.pop()
+    }
+
+    /// Gets the layers, from the bottom to the top.
+    pub fn layers(&self) -> &[Color] {
+        &self.layers
+    }
+
+    /// Composites the layers from the bottom to the top with `Color::blend_over`.
+    ///
+    /// An empty stack is transparent.
+    pub fn flatten(&self) -> Color {
+        self.layers.iter()
+            .fold(Color::TRANSPARENT, |below, layer| layer.blend_over(&below))
+    }
+
+    /// Composites the flattened layers over every pixel of the buffer.
+    pub fn flatten_into(&self, buffer: &mut Buffer) -> Result<(), BorrowError> {
+        let color = self.flatten();
+        if color.is_transparent() {
+            return Ok(())
+        }
+        for (_, _, mut pixel) in buffer.pixels_mut()?.iter_mut() {
+            pixel.blend(color);
+        }
+        Ok(())
+    }
+}
+
+impl FromIterator<Color> for LayerStack {
+    fn from_iter<I: IntoIterator<Item=Color>>(iter: I) -> Self {
+        LayerStack { layers: iter.into_iter().collect() }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn flatten() {
+        let mut stack = LayerStack::new();
+        assert_eq!(Color::TRANSPARENT, stack.flatten());
+        let (bottom, top) = (Color::rgba(255, 0, 0, 128), Color::rgba(0, 0, 255, 64));
+        stack.push(bottom);
+        assert_eq!(bottom, stack.flatten());
+        stack.push(top);
+        assert_eq!(top.blend_over(&bottom.blend_over(&Color::TRANSPARENT)), stack.flatten());
+        assert_eq!(top.blend_over(&bottom), stack.flatten());
+        // an opaque layer hides everything below it
+        stack.push(Color::GREEN);
+        assert_eq!(Color::GREEN, stack.flatten());
+        assert_eq!(Some(Color::GREEN), stack.pop());
+        assert_eq!(&[bottom, top], stack.layers());
+        assert_eq!(stack, vec![bottom, top].into_iter().collect());
+    }
+
+    #[test]
+    fn flatten_into() {
+        let stack: LayerStack = vec![Color::rgba(255, 0, 0, 128), Color::rgba(0, 0, 255, 64)]
+            .into_iter()
+            .collect();
+        let background = Color::rgba(0, 255, 0, 255);
+        let mut buffer = Buffer::new(2, 2).unwrap();
+        buffer.fill_rect(0, 0, 1, 2, background).unwrap();
+        stack.flatten_into(&mut buffer).unwrap();
+        let flattened = stack.flatten();
+        for (x, _, pixel) in buffer.pixels_mut().unwrap().iter_mut() {
+            if x == 0 {
+                assert_eq!(flattened.blend_over(&background), pixel.get());
+            } else {
+                assert_eq!(flattened, pixel.get());
+            }
+        }
+        // nothing to draw
+        LayerStack::new().flatten_into(&mut buffer).unwrap();
+    }
+}
@@ -0,0 +1,119 @@
+//! Lookup tables that map every channel value to a new one, eg for gamma
+//! correction of a whole `Buffer`.
+#![allow(dead_code)] // Nothing gamma corrects a Buffer yet
+
+use cairo::BorrowError;
+
+use super::buffer::Buffer;
+use super::color::Color;
+
+/// A table per channel, which maps each of its 256 values to a new one.
+#[derive(Clone)]
+pub struct ColorLut {
+    red: [u8; 256],
+    green: [u8; 256],
+    blue: [u8; 256],
+    alpha: [u8; 256]
+}
+
+impl ColorLut {
+    /// Makes a lookup table out of a table for each channel.
+    pub fn new(red: [u8; 256], green: [u8; 256], blue: [u8; 256], alpha: [u8; 256]) -> Self {
+        ColorLut { red, green, blue, alpha }
+    }
+
+    /// A lookup table that maps every value to itself.
+    pub fn identity() -> Self {
+        let table = identity_table();
+        ColorLut::new(table, table, table, table)
+    }
+
+    /// A lookup table that raises the red, green and blue channels (from
+    /// 0.0 to 1.0) to the power of `gamma`, so a gamma above 1.0 darkens the
+    /// midtones and one below 1.0 brightens them. Black and white stay
+    /// the same, the alpha channel is kept.
+    pub fn gamma(gamma: f32) -> Self {
+        let mut table = [0; 256];
+        for (value, entry) in table.iter_mut().enumerate() {
+            // rounded the same way as the channels of any calculated color
+            *entry = Color::clamp_channel((value as f32 / 255.0).powf(gamma) * 255.0);
+        }
+        ColorLut::new(table, table, table, identity_table())
+    }
+
+    /// Looks up every channel of the color in its table.
+    pub fn apply(&self, color: Color) -> Color {
+        let [red, green, blue, alpha] = color.channels();
+        Color::from_channels([self.red[red as usize],
+                              self.green[green as usize],
+                              self.blue[blue as usize],
+                              self.alpha[alpha as usize]])
+    }
+}
+
+/// Runs every pixel of the buffer through the lookup table.
+///
+/// The tables are applied to the unpremultiplied channels. Pixels the
+/// table maps to themselves aren't written back, so they keep their exact
+/// premultiplied values.
+pub fn apply_lut(buffer: &mut Buffer, lut: &ColorLut) -> Result<(), BorrowError> {
+    for (_, _, mut pixel) in buffer.pixels_mut()?.iter_mut() {
+        let color = pixel.get();
+        let mapped = lut.apply(color);
+        if mapped != color {
+            pixel.set(mapped);
+        }
+    }
+    Ok(())
+}
+
+/// A table that maps every value to itself.
+fn identity_table() -> [u8; 256] {
+    let mut table = [0; 256];
+    for (value, entry) in table.iter_mut().enumerate() {
+        *entry = value as u8;
+    }
+    table
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Reads all the packed pixels of the buffer, row by row.
+    fn pixels(buffer: &mut Buffer) -> Vec<u32> {
+        let (width, height) = (buffer.width(), buffer.height());
+        (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| buffer.pixel(x, y).unwrap().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn identity() {
+        let mut buffer = Buffer::new(4, 2).unwrap();
+        buffer.fill_rect(0, 0, 1, 2, Color::rgba(200, 100, 50, 255)).unwrap();
+        buffer.fill_rect(1, 0, 1, 2, Color::rgba(3, 7, 250, 33)).unwrap();
+        buffer.fill_rect(2, 0, 1, 1, Color::rgba(255, 255, 255, 1)).unwrap();
+        let before = pixels(&mut buffer);
+        apply_lut(&mut buffer, &ColorLut::identity()).unwrap();
+        assert_eq!(before, pixels(&mut buffer));
+    }
+
+    #[test]
+    fn gamma() {
+        let lut = ColorLut::gamma(2.2);
+        assert_eq!(Color::rgba(56, 0, 255, 128), lut.apply(Color::rgba(128, 0, 255, 128)));
+        assert_eq!(Color::rgba(128, 128, 128, 7), ColorLut::gamma(1.0)
+            .apply(Color::rgba(128, 128, 128, 7)));
+        assert_eq!(Color::rgba(186, 186, 186, 255), ColorLut::gamma(1.0 / 2.2)
+            .apply(Color::rgba(128, 128, 128, 255)));
+
+        let mut buffer = Buffer::new(2, 1).unwrap();
+        buffer.fill_rect(0, 0, 1, 1, Color::rgba(128, 64, 255, 255)).unwrap();
+        apply_lut(&mut buffer, &lut).unwrap();
+        assert_eq!(Some(Color::rgba(56, 12, 255, 255).as_cairo_argb32()),
+                   buffer.pixel(0, 0).unwrap());
+        assert_eq!(Some(0), buffer.pixel(1, 0).unwrap());
+    }
+}
@@ -1,13 +1,36 @@
 mod renderable;
+mod adjust;
+mod ansi;
+mod buffer;
 mod draw;
 mod color;
+mod color_names;
+mod color_space;
+mod gradient;
+mod layer_stack;
+mod lut;
+mod palette;
+mod pixel;
+mod text;
+mod theme;
 pub mod screen_scrape;
 
 use cairo::{self, ImageSurface};
 use gdk_pixbuf::Pixbuf;
 pub use self::renderable::Renderable;
+pub use self::adjust::ColorAdjust;
+pub use self::buffer::{Buffer, CheckerboardError, draw_border};
 pub use self::draw::{Drawable, DrawErr, BaseDraw};
-pub use self::color::Color;
+pub use self::color::{Color, ColorParseError, GammaMode, HexOrder, HexPrefix,
+                      InvalidPixelLength, PackedColor, ParseMode};
+pub use self::color_space::{ColorSpace, Linear, Srgb, TaggedColor};
+pub use self::gradient::{Gradient, GradientError};
+pub use self::layer_stack::LayerStack;
+pub use self::lut::{apply_lut, ColorLut};
+pub use self::palette::{ColorRole, Palette, ReferenceCycle, UnresolvedColor};
+pub use self::pixel::{PixelRef, Pixels};
+pub use self::text::{draw_text, TextBox};
+pub use self::theme::{parse_scheme, ThemeError};
 
 
 /// Using a Pixbuf buffer, loads the data into a Cairo surface.
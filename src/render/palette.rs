@@ -0,0 +1,345 @@
+//! A palette of the colors used for the different parts of the compositor.
+#![allow(dead_code)] // The borders don't take their colors from a palette yet
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::iter::FromIterator;
+
+use super::color::Color;
+
+/// The different things the compositor draws with a themeable color.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum ColorRole {
+    /// The border of the focused window.
+    FocusedBorder,
+    /// The borders of the windows that aren't focused.
+    UnfocusedBorder,
+    /// The title bar background of the focused window.
+    FocusedTitle,
+    /// The title bar background of the windows that aren't focused.
+    UnfocusedTitle,
+    /// The text in the title bars.
+    TitleFont,
+    /// The background behind all windows.
+    Background,
+    /// The border of windows that demand attention.
+    Urgent
+}
+
+/// All the roles, in the order they are declared.
+const ALL_ROLES: [ColorRole; 7] = [
+    ColorRole::FocusedBorder,
+    ColorRole::UnfocusedBorder,
+    ColorRole::FocusedTitle,
+    ColorRole::UnfocusedTitle,
+    ColorRole::TitleFont,
+    ColorRole::Background,
+    ColorRole::Urgent
+];
+
+impl ColorRole {
+    /// Gets the role with the given name, as used in themes (eg "focused_border").
+    pub fn from_name(name: &str) -> Option<ColorRole> {
+        match name {
+            "focused_border" => Some(ColorRole::FocusedBorder),
+            "unfocused_border" => Some(ColorRole::UnfocusedBorder),
+            "focused_title" => Some(ColorRole::FocusedTitle),
+            "unfocused_title" => Some(ColorRole::UnfocusedTitle),
+            "title_font" => Some(ColorRole::TitleFont),
+            "background" => Some(ColorRole::Background),
+            "urgent" => Some(ColorRole::Urgent),
+            _ => None
+        }
+    }
+
+    /// Gets the name of the role, the inverse of `from_name`.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            ColorRole::FocusedBorder => "focused_border",
+            ColorRole::UnfocusedBorder => "unfocused_border",
+            ColorRole::FocusedTitle => "focused_title",
+            ColorRole::UnfocusedTitle => "unfocused_title",
+            ColorRole::TitleFont => "title_font",
+            ColorRole::Background => "background",
+            ColorRole::Urgent => "urgent"
+        }
+    }
+
+    /// The color used for the role if the palette doesn't set one.
+    ///
+    /// These match the defaults of the border and title bar options.
+    pub fn default_color(&self) -> Color {
+        match *self {
+            ColorRole::FocusedBorder |
+            ColorRole::UnfocusedBorder |
+            ColorRole::FocusedTitle |
+            ColorRole::UnfocusedTitle |
+            ColorRole::Background => Color::BLACK,
+            ColorRole::TitleFont => Color::WHITE,
+            ColorRole::Urgent => Color::RED
+        }
+    }
+}
+
+/// A color of a theme, which may be the color of another role,
+/// eg "@focused_border" to use the same color as the focused border.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum UnresolvedColor {
+    /// The color itself.
+    Literal(Color),
+    /// The color of the role, once `Palette::resolve` is called.
+    Ref(ColorRole)
+}
+
+/// The references between the roles of a `Palette` go around in a circle,
+/// so they can't be resolved.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ReferenceCycle {
+    /// A role whose reference leads into the cycle.
+    pub role: ColorRole
+}
+
+impl fmt::Display for ReferenceCycle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the color of {} is part of a reference cycle", self.role.name())
+    }
+}
+
+impl Error for ReferenceCycle {}
+
+/// Maps the `ColorRole`s to the colors they should be drawn with.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Palette {
+    colors: HashMap<ColorRole, Color>,
+    /// Colors with names that aren't a `ColorRole`, eg for use by Lua.
+    custom: HashMap<String, Color>,
+    /// The roles that use the color of another role, until `resolve` is called.
+    refs: HashMap<ColorRole, ColorRole>,
+    /// The custom colors that use the color of a role, until `resolve` is called.
+    custom_refs: HashMap<String, ColorRole>
+}
+
+impl Palette {
+    /// Creates a palette where every role has its default color.
+    pub fn new() -> Self {
+        Palette::default()
+    }
+
+    /// Gets the color of the role, or its default color if it is unset.
+    pub fn get(&self, role: ColorRole) -> Color {
+        self.colors.get(&role).cloned()
+            .unwrap_or_else(|| role.default_color())
+    }
+
+    /// Sets the color of the role, returning the color that was set before.
+    pub fn set(&mut self, role: ColorRole, color: Color) -> Option<Color> {
+        self.refs.remove(&role);
+        self.colors.insert(role, color)
+    }
+
+    /// Sets the color of the role, which may be a reference to another role.
+    ///
+    /// References are only followed by `resolve`, until then `get` returns
+    /// the color the role had before.
+    pub fn set_unresolved(&mut self, role: ColorRole, color: UnresolvedColor) {
+        match color {
+            UnresolvedColor::Literal(color) => { self.set(role, color); },
+            UnresolvedColor::Ref(target) => { self.refs.insert(role, target); }
+        }
+    }
+
+    /// Like `set_unresolved`, but for a color with a name that isn't a `ColorRole`.
+    pub fn set_custom_unresolved<S: Into<String>>(&mut self, name: S, color: UnresolvedColor) {
+        let name = name.into();
+        match color {
+            UnresolvedColor::Literal(color) => { self.set_custom(name, color); },
+            UnresolvedColor::Ref(target) => { self.custom_refs.insert(name, target); }
+        }
+    }
+
+    /// Replaces all references to other roles (set with `set_unresolved`)
+    /// with the colors of those roles, following chains of references.
+    ///
+    /// If the references go around in a circle, the palette is left unchanged.
+    pub fn resolve(&mut self) -> Result<(), ReferenceCycle> {
+        let mut resolved = Vec::new();
+        for &role in ALL_ROLES.iter().filter(|role| self.refs.contains_key(role)) {
+            let mut visited = vec![role];
+            let mut current = role;
+            while let Some(&target) = self.refs.get(&current) {
+                if visited.contains(&target) {
+                    return Err(ReferenceCycle { role })
+                }
+                visited.push(target);
+                current = target;
+            }
+            resolved.push((role, self.get(current)));
+        }
+        for (role, color) in resolved {
+            self.set(role, color);
+        }
+        let custom_refs: Vec<_> = self.custom_refs.drain().collect();
+        for (name, target) in custom_refs {
+            let color = self.get(target);
+            self.set_custom(name, color);
+        }
+        Ok(())
+    }
+
+    /// Finds the role whose color looks the most like `color`, according to
+    /// `Color::distance`. If multiple roles have the same color, the one
+    /// declared first in `ColorRole` is used.
+    pub fn nearest(&self, color: Color) -> ColorRole {
+        let mut nearest = ALL_ROLES[0];
+        let mut nearest_distance = color.distance(&self.get(nearest));
+        for &role in &ALL_ROLES[1..] {
+            let distance = color.distance(&self.get(role));
+            if distance < nearest_distance {
+                nearest = role;
+                nearest_distance = distance;
+            }
+        }
+        nearest
+    }
+
+    /// Gets the color with a name that isn't a `ColorRole`.
+    pub fn get_custom(&self, name: &str) -> Option<Color> {
+        self.custom.get(name).cloned()
+    }
+
+    /// Sets a color with a name that isn't a `ColorRole`,
+    /// returning the color that was set before.
+    pub fn set_custom<S: Into<String>>(&mut self, name: S, color: Color) -> Option<Color> {
+        let name = name.into();
+        self.custom_refs.remove(&name);
+        self.custom.insert(name, color)
+    }
+}
+
+impl FromIterator<(ColorRole, Color)> for Palette {
+    fn from_iter<I>(iter: I) -> Self
+        where I: IntoIterator<Item=(ColorRole, Color)>
+    {
+        Palette { colors: iter.into_iter().collect(), .. Palette::default() }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn defaults() {
+        let palette = Palette::new();
+        assert_eq!(Color::rgba(0, 0, 0, 255), palette.get(ColorRole::FocusedBorder));
+        assert_eq!(Color::rgba(0, 0, 0, 255), palette.get(ColorRole::UnfocusedBorder));
+        assert_eq!(Color::rgba(255, 255, 255, 255), palette.get(ColorRole::TitleFont));
+        assert_eq!(Color::rgba(255, 0, 0, 255), palette.get(ColorRole::Urgent));
+    }
+
+    #[test]
+    fn set() {
+        let blue = Color::rgba(0, 0, 255, 255);
+        let green = Color::rgba(0, 255, 0, 255);
+        let mut palette = Palette::new();
+        assert_eq!(None, palette.set(ColorRole::FocusedBorder, blue));
+        assert_eq!(blue, palette.get(ColorRole::FocusedBorder));
+        assert_eq!(Some(blue), palette.set(ColorRole::FocusedBorder, green));
+        assert_eq!(green, palette.get(ColorRole::FocusedBorder));
+        // other roles keep their defaults
+        assert_eq!(ColorRole::UnfocusedBorder.default_color(),
+                   palette.get(ColorRole::UnfocusedBorder));
+    }
+
+    #[test]
+    fn custom() {
+        let blue = Color::rgba(0, 0, 255, 255);
+        let mut palette = Palette::new();
+        assert_eq!(None, palette.get_custom("accent"));
+        assert_eq!(None, palette.set_custom("accent", blue));
+        assert_eq!(Some(blue), palette.get_custom("accent"));
+        assert_eq!(Some(blue), palette.set_custom("accent".to_string(), Color::default()));
+        assert_eq!(Some(ColorRole::Urgent), ColorRole::from_name("urgent"));
+        assert_eq!(None, ColorRole::from_name("accent"));
+        for &role in ALL_ROLES.iter() {
+            assert_eq!(Some(role), ColorRole::from_name(role.name()));
+        }
+    }
+
+    #[test]
+    fn resolve() {
+        let blue = Color::rgba(0, 0, 255, 255);
+        let mut palette = Palette::new();
+        palette.set_unresolved(ColorRole::FocusedBorder, UnresolvedColor::Literal(blue));
+        palette.set_unresolved(ColorRole::FocusedTitle,
+                               UnresolvedColor::Ref(ColorRole::FocusedBorder));
+        palette.set_unresolved(ColorRole::Urgent, UnresolvedColor::Ref(ColorRole::FocusedTitle));
+        palette.set_unresolved(ColorRole::Background, UnresolvedColor::Ref(ColorRole::TitleFont));
+        palette.set_custom_unresolved("accent", UnresolvedColor::Ref(ColorRole::Urgent));
+        // nothing changes until the references are resolved
+        assert_eq!(ColorRole::Urgent.default_color(), palette.get(ColorRole::Urgent));
+        assert_eq!(None, palette.get_custom("accent"));
+        assert_eq!(Ok(()), palette.resolve());
+        assert_eq!(blue, palette.get(ColorRole::FocusedBorder));
+        assert_eq!(blue, palette.get(ColorRole::FocusedTitle));
+        assert_eq!(blue, palette.get(ColorRole::Urgent));
+        assert_eq!(Some(blue), palette.get_custom("accent"));
+        // references to unset roles use their default color
+        assert_eq!(ColorRole::TitleFont.default_color(), palette.get(ColorRole::Background));
+        // setting a color replaces a reference
+        palette.set_unresolved(ColorRole::Urgent, UnresolvedColor::Ref(ColorRole::TitleFont));
+        palette.set(ColorRole::Urgent, Color::GREEN);
+        assert_eq!(Ok(()), palette.resolve());
+        assert_eq!(Color::GREEN, palette.get(ColorRole::Urgent));
+    }
+
+    #[test]
+    fn resolve_cycle() {
+        let mut palette = Palette::new();
+        palette.set_unresolved(ColorRole::FocusedBorder, UnresolvedColor::Literal(Color::BLUE));
+        palette.set_unresolved(ColorRole::Urgent, UnresolvedColor::Ref(ColorRole::FocusedTitle));
+        palette.set_unresolved(ColorRole::FocusedTitle, UnresolvedColor::Ref(ColorRole::Urgent));
+        let unresolved = palette.clone();
+        assert_eq!(Err(ReferenceCycle { role: ColorRole::FocusedTitle }), palette.resolve());
+        assert_eq!(unresolved, palette);
+        assert_eq!("the color of focused_title is part of a reference cycle",
+                   palette.resolve().unwrap_err().to_string());
+        // a role referring to itself
+        let mut palette = Palette::new();
+        palette.set_unresolved(ColorRole::Background, UnresolvedColor::Ref(ColorRole::Background));
+        assert_eq!(Err(ReferenceCycle { role: ColorRole::Background }), palette.resolve());
+        // a chain leading into a cycle
+        palette.set_unresolved(ColorRole::FocusedBorder, UnresolvedColor::Ref(ColorRole::Background));
+        assert_eq!(Err(ReferenceCycle { role: ColorRole::FocusedBorder }), palette.resolve());
+    }
+
+    #[test]
+    fn nearest() {
+        let mut palette = Palette::new();
+        palette.set(ColorRole::FocusedBorder, Color::rgba(0, 0, 255, 255));
+        palette.set(ColorRole::Background, Color::rgba(128, 128, 128, 255));
+        assert_eq!(ColorRole::Urgent, palette.nearest(Color::rgba(200, 30, 20, 255)));
+        assert_eq!(ColorRole::FocusedBorder, palette.nearest(Color::rgba(10, 20, 230, 255)));
+        assert_eq!(ColorRole::Background, palette.nearest(Color::rgba(120, 130, 125, 255)));
+        assert_eq!(ColorRole::TitleFont, palette.nearest(Color::rgba(250, 250, 250, 255)));
+        // the other title and background roles are black as well, but declared later
+        assert_eq!(ColorRole::UnfocusedBorder, palette.nearest(Color::rgba(0, 0, 0, 255)));
+    }
+
+    #[test]
+    fn from_iter() {
+        let blue = Color::rgba(0, 0, 255, 255);
+        let gray = Color::rgba(128, 128, 128, 255);
+        let palette: Palette = vec![(ColorRole::FocusedBorder, blue),
+                                    (ColorRole::Background, gray)]
+            .into_iter().collect();
+        assert_eq!(blue, palette.get(ColorRole::FocusedBorder));
+        assert_eq!(gray, palette.get(ColorRole::Background));
+        assert_eq!(ColorRole::Urgent.default_color(), palette.get(ColorRole::Urgent));
+        // later entries win
+        let palette: Palette = vec![(ColorRole::Urgent, blue), (ColorRole::Urgent, gray)]
+            .into_iter().collect();
+        assert_eq!(gray, palette.get(ColorRole::Urgent));
+    }
+}
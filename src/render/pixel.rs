@@ -0,0 +1,66 @@
+//! Reading and writing the pixels of a `Buffer` as `Color`s, eg for effects.
+#![allow(dead_code)] // No effect reads or writes single pixels yet
+
+use std::convert::TryFrom;
+
+use cairo::ImageSurfaceData;
+
+use super::color::Color;
+
+/// The number of bytes a pixel takes up in a `Format::ARgb32` surface.
+pub const BYTES_PER_PIXEL: usize = 4;
+
+/// The pixels of a `Buffer`, borrowed with `Buffer::pixels_mut`.
+///
+/// Cairo is told the pixels changed when this is dropped.
+pub struct Pixels<'a> {
+    data: ImageSurfaceData<'a>,
+    width: usize,
+    stride: usize
+}
+
+impl<'a> Pixels<'a> {
+    /// Wraps the data of a `Format::ARgb32` surface with the given width
+    /// in pixels and stride in bytes.
+    pub fn new(data: ImageSurfaceData<'a>, width: usize, stride: usize) -> Self {
+        Pixels { data, width, stride }
+    }
+
+    /// Iterates over all pixels row by row, with their x and y position.
+    ///
+    /// The padding at the end of the rows (if the stride is bigger than
+    /// the width) is skipped.
+    pub fn iter_mut<'b>(&'b mut self) -> impl Iterator<Item=(u32, u32, PixelRef<'b>)> + 'b {
+        let row_len = self.width * BYTES_PER_PIXEL;
+        // The stride is only 0 if there is no data to split
+        self.data.chunks_mut(self.stride.max(1)).enumerate().flat_map(move |(y, row)| {
+            row[..row_len].chunks_mut(BYTES_PER_PIXEL).enumerate()
+                .map(move |(x, bytes)| (x as u32, y as u32, PixelRef { bytes }))
+        })
+    }
+}
+
+/// A pixel of a `Buffer`.
+///
+/// The pixel is stored premultiplied, so some precision is lost
+/// when writing colors that aren't opaque.
+pub struct PixelRef<'a> {
+    bytes: &'a mut [u8]
+}
+
+impl<'a> PixelRef<'a> {
+    /// Reads the color of the pixel.
+    pub fn get(&self) -> Color {
+        Color::try_from(&*self.bytes).expect("A pixel has 4 bytes")
+    }
+
+    /// Replaces the color of the pixel.
+    pub fn set(&mut self, color: Color) {
+        self.bytes.copy_from_slice(&color.as_cairo_argb32().to_ne_bytes());
+    }
+
+    /// Composites the color over the pixel, with `Color::alpha_blend_onto`.
+    pub fn blend(&mut self, color: Color) {
+        color.alpha_blend_onto(self.bytes).expect("A pixel has 4 bytes");
+    }
+}
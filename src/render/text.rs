@@ -0,0 +1,84 @@
+//! Drawing text onto a `Buffer` with Pango.
+#![allow(dead_code)] // Nothing draws text into a Buffer yet
+
+use cairo::{self, Context};
+use pango::{self, LayoutExt};
+use pangocairo;
+
+use super::buffer::Buffer;
+use super::color::Color;
+
+/// The box, in pixels of the buffer, that drawn text takes up.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct TextBox {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32
+}
+
+/// Draws `text` over what was there before, with the top left corner of
+/// its first line at `x`, `y`.
+///
+/// `font` is a Pango font description, eg "Sans Bold 12". Returns the box
+/// the laid out text takes up, which includes the line spacing and so can
+/// be a bit bigger than the pixels that were drawn. Empty text draws
+/// nothing and takes up an empty box at `x`, `y`.
+pub fn draw_text(buffer: &mut Buffer, text: &str, x: i32, y: i32, font: &str, color: Color)
+                 -> Result<TextBox, cairo::Status> {
+    if text.is_empty() {
+        return Ok(TextBox { x, y, width: 0, height: 0 })
+    }
+    let context = Context::new(buffer.get_surface());
+    let layout = pangocairo::functions::create_layout(&context)
+        .ok_or(cairo::Status::NoMemory)?;
+    layout.set_font_description(Some(&pango::FontDescription::from_string(font)));
+    layout.set_text(text);
+    let (red, green, blue, alpha) = color.to_cairo_rgba();
+    context.set_source_rgba(red, green, blue, alpha);
+    context.move_to(x as f64, y as f64);
+    pangocairo::functions::show_layout(&context, &layout);
+    match context.status() {
+        cairo::Status::Success => {},
+        err => return Err(err)
+    }
+    let (_, logical) = layout.get_pixel_extents();
+    Ok(TextBox {
+        x: x + logical.x,
+        y: y + logical.y,
+        width: logical.width.max(0) as u32,
+        height: logical.height.max(0) as u32
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Counts the pixels of the buffer that aren't fully transparent.
+    fn drawn(buffer: &mut Buffer) -> usize {
+        let (width, height) = (buffer.width(), buffer.height());
+        (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .filter(|&(x, y)| buffer.pixel(x, y).unwrap().unwrap() != 0)
+            .count()
+    }
+
+    #[test]
+    fn draws_text() {
+        let mut buffer = Buffer::new(120, 40).unwrap();
+        let text_box = draw_text(&mut buffer, "Way Cooler", 4, 4, "Sans 12", Color::WHITE)
+            .unwrap();
+        assert!(drawn(&mut buffer) > 0);
+        assert!(text_box.width > 0 && text_box.height > 0, "{:?}", text_box);
+        assert!(text_box.x >= 4 && text_box.y >= 4, "{:?}", text_box);
+    }
+
+    #[test]
+    fn empty_text() {
+        let mut buffer = Buffer::new(120, 40).unwrap();
+        let text_box = draw_text(&mut buffer, "", 4, 8, "Sans 12", Color::WHITE).unwrap();
+        assert_eq!(0, drawn(&mut buffer));
+        assert_eq!(TextBox { x: 4, y: 8, width: 0, height: 0 }, text_box);
+    }
+}
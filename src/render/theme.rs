@@ -0,0 +1,159 @@
+//! Loading a whole `Palette` at once from a color scheme.
+//!
+//! A scheme has one `name = value` pair per line, where the value is
+//! anything `Color::parse` understands, or `@` and the name of a role to use
+//! the same color as that role. Blank lines and lines starting with `#`
+//! are ignored:
+//!
+//! ```text
+//! # solarized-ish
+//! focused_border = #268bd2
+//! focused_title  = @focused_border
+//! background     = rgb(0, 43, 54)
+//! accent         = orange
+//! ```
+#![allow(dead_code)] // The config doesn't load a color scheme yet
+
+use std::error::Error;
+use std::fmt;
+
+use super::color::{Color, ColorParseError};
+use super::palette::{ColorRole, Palette, ReferenceCycle, UnresolvedColor};
+
+/// The ways parsing a color scheme can go wrong.
+///
+/// The line numbers start at 1.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ThemeError {
+    /// The line is not of the form `name = value`.
+    MalformedLine { line: usize },
+    /// The value of the line is not a valid color.
+    InvalidColor { line: usize, reason: ColorParseError },
+    /// The value of the line refers to a role that doesn't exist.
+    UnknownReference { line: usize },
+    /// The references between the roles go around in a circle.
+    ReferenceCycle(ReferenceCycle)
+}
+
+impl fmt::Display for ThemeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ThemeError::MalformedLine { line } =>
+                write!(f, "line {} is not of the form `name = color`", line),
+            ThemeError::InvalidColor { line, ref reason } =>
+                write!(f, "the color on line {} is invalid: {}", line, reason),
+            ThemeError::UnknownReference { line } =>
+                write!(f, "line {} refers to an unknown role", line),
+            ThemeError::ReferenceCycle(ref cycle) => write!(f, "{}", cycle)
+        }
+    }
+}
+
+impl Error for ThemeError {}
+
+/// Parses a color scheme into a `Palette`.
+///
+/// Names of `ColorRole`s (eg "focused_border") set that role, all other
+/// names are kept as custom colors of the palette. If a name is used
+/// multiple times, the last one wins.
+///
+/// References to other roles are resolved once the whole scheme is parsed,
+/// so a role can refer to one that is set further down.
+pub fn parse_scheme(scheme: &str) -> Result<Palette, ThemeError> {
+    let mut palette = Palette::new();
+    for (index, line) in scheme.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue
+        }
+        let mut parts = line.splitn(2, '=');
+        let name = parts.next().unwrap_or("").trim();
+        let value = match parts.next() {
+            Some(value) if !name.is_empty() => value,
+            _ => return Err(ThemeError::MalformedLine { line: line_number })
+        };
+        let color = parse_value(value, line_number)?;
+        match ColorRole::from_name(name) {
+            Some(role) => palette.set_unresolved(role, color),
+            None => palette.set_custom_unresolved(name, color)
+        }
+    }
+    palette.resolve().map_err(ThemeError::ReferenceCycle)?;
+    Ok(palette)
+}
+
+/// Parses the value of a line, either a color or a reference to a role.
+fn parse_value(value: &str, line: usize) -> Result<UnresolvedColor, ThemeError> {
+    match value.trim().strip_prefix('@') {
+        Some(name) => ColorRole::from_name(name)
+            .map(UnresolvedColor::Ref)
+            .ok_or(ThemeError::UnknownReference { line }),
+        None => Color::try_parse(value)
+            .map(UnresolvedColor::Literal)
+            .map_err(|reason| ThemeError::InvalidColor { line, reason })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let palette = parse_scheme("
+            # a comment
+            focused_border = #0000ff
+
+            background=rgb(128, 128, 128)
+              accent  =  orange
+            urgent = #00ff00
+            urgent = #ff00ff
+        ").unwrap();
+        assert_eq!(Color::rgba(0, 0, 255, 255), palette.get(ColorRole::FocusedBorder));
+        assert_eq!(Color::rgba(128, 128, 128, 255), palette.get(ColorRole::Background));
+        assert_eq!(Color::rgba(255, 0, 255, 255), palette.get(ColorRole::Urgent));
+        assert_eq!(Some(Color::rgba(255, 165, 0, 255)), palette.get_custom("accent"));
+        // unset roles keep their defaults
+        assert_eq!(ColorRole::TitleFont.default_color(), palette.get(ColorRole::TitleFont));
+        assert_eq!(Ok(Palette::new()), parse_scheme(""));
+    }
+
+    #[test]
+    fn references() {
+        let palette = parse_scheme("
+            focused_title = @focused_border
+            focused_border = #0000ff
+            urgent = @title_font
+            accent = @focused_title
+        ").unwrap();
+        let blue = Color::rgba(0, 0, 255, 255);
+        assert_eq!(blue, palette.get(ColorRole::FocusedBorder));
+        assert_eq!(blue, palette.get(ColorRole::FocusedTitle));
+        assert_eq!(Some(blue), palette.get_custom("accent"));
+        assert_eq!(ColorRole::TitleFont.default_color(), palette.get(ColorRole::Urgent));
+        assert_eq!(Err(ThemeError::UnknownReference { line: 2 }),
+                   parse_scheme("urgent = red\nbackground = @accent"));
+        let cycle = ReferenceCycle { role: ColorRole::FocusedBorder };
+        assert_eq!(Err(ThemeError::ReferenceCycle(cycle)),
+                   parse_scheme("focused_border = @urgent\nurgent = @focused_border"));
+        assert_eq!("the color of background is part of a reference cycle",
+                   parse_scheme("background = @background").unwrap_err().to_string());
+    }
+
+    #[test]
+    fn malformed() {
+        assert_eq!(Err(ThemeError::MalformedLine { line: 3 }),
+                   parse_scheme("# colors\nbackground = #000000\nfocused_border #ffffff"));
+        assert_eq!(Err(ThemeError::MalformedLine { line: 1 }), parse_scheme(" = #ffffff"));
+        assert_eq!(Err(ThemeError::InvalidColor { line: 2,
+                                                  reason: ColorParseError::EmptyInput }),
+                   parse_scheme("urgent = red\nbackground =\n"));
+        let invalid_length = ColorParseError::InvalidLength { got: 5 };
+        assert_eq!(Err(ThemeError::InvalidColor { line: 1, reason: invalid_length }),
+                   parse_scheme("background = #12345"));
+        assert_eq!("the color on line 1 is invalid: \
+                    the color has 5 digits, expected 3, 4, 6, 8, 12 or 16",
+                   parse_scheme("background = #12345").unwrap_err().to_string());
+    }
+}